@@ -0,0 +1,222 @@
+// src/behavior.rs
+// Per-boid behavioural state machine: each boid is flocking, fleeing, foraging or
+// resting, switches state when the conditions of a transition hold (neighbors,
+// predators, energy, time spent in the state), and each state weights the rules.
+
+use ggez::glam::Vec2;
+use ggez::graphics::Color;
+
+use crate::boids::Boid;
+use crate::config::{BehaviorConfig, BehaviorState, StateWeights, TransitionConfig};
+use crate::obstacles::Obstacle;
+
+// --- BehaviorState Implementation ---
+
+impl BehaviorState {
+    pub const COUNT: usize = 4;
+
+    pub const ALL: [BehaviorState; BehaviorState::COUNT] = [
+        BehaviorState::Flocking,
+        BehaviorState::Fleeing,
+        BehaviorState::Foraging,
+        BehaviorState::Resting,
+    ];
+
+    /// Returns the position of the state in BehaviorState::ALL.
+    pub fn index(self) -> usize {
+        self as usize
+    }
+
+    /// Returns the name of the state, as written in boids.yaml.
+    pub fn name(self) -> &'static str {
+        match self {
+            BehaviorState::Flocking => "flocking",
+            BehaviorState::Fleeing => "fleeing",
+            BehaviorState::Foraging => "foraging",
+            BehaviorState::Resting => "resting",
+        }
+    }
+
+    /// Returns the color boids in this state are drawn with.
+    pub fn color(self) -> Color {
+        match self {
+            BehaviorState::Flocking => Color::new(0.3, 0.6, 1.0, 1.0),
+            BehaviorState::Fleeing => Color::new(1.0, 0.3, 0.2, 1.0),
+            BehaviorState::Foraging => Color::new(0.3, 0.9, 0.3, 1.0),
+            BehaviorState::Resting => Color::new(0.6, 0.6, 0.6, 1.0),
+        }
+    }
+}
+
+// --- Senses Struct Definition ---
+
+// What a boid perceives during a step, tested by the transition conditions
+#[derive(Debug, Clone, Copy)]
+pub struct Senses {
+    pub neighbors: usize,               // neighbors in visible range
+    pub predator_distance: Option<f32>, // distance to the closest predator, if any
+}
+
+// --- StateMachine Struct Definition ---
+
+#[derive(Debug, Clone)]
+pub struct StateMachine {
+    initial: BehaviorState,
+    weights: [StateWeights; BehaviorState::COUNT], // rule weights, by state index
+    transitions: Vec<TransitionConfig>,
+}
+
+// --- StateMachine Implementation ---
+
+impl StateMachine {
+    /// Creates a new StateMachine from its configuration.
+    ///
+    /// # Arguments
+    ///
+    /// * 'config' - The BehaviorConfig read from boids.yaml.
+    ///
+    /// # Returns
+    ///
+    /// * 'Self' - A new StateMachine instance.
+    pub fn new(config: &BehaviorConfig) -> Self {
+        StateMachine {
+            initial: config.initial,
+            weights: [
+                config.flocking,
+                config.fleeing,
+                config.foraging,
+                config.resting,
+            ],
+            transitions: config.transitions.clone(),
+        }
+    }
+
+    /// Returns the state of new boids.
+    pub fn initial(&self) -> BehaviorState {
+        self.initial
+    }
+
+    /// Returns the rule weights of a state.
+    pub fn weights(&self, state: BehaviorState) -> &StateWeights {
+        &self.weights[state.index()]
+    }
+
+    /// Returns the state a boid switches to, following the first transition from
+    /// its state whose conditions all hold.
+    ///
+    /// # Arguments
+    ///
+    /// * 'boid' - The boid.
+    /// * 'senses' - What the boid perceives this step.
+    ///
+    /// # Returns
+    ///
+    /// * 'Option<BehaviorState>' - The new state, or None if the boid stays in its state.
+    pub fn next_state(&self, boid: &Boid, senses: &Senses) -> Option<BehaviorState> {
+        self.transitions
+            .iter()
+            .filter(|t| t.to != boid.behavior && t.from.is_none_or(|from| from == boid.behavior))
+            .find(|t| {
+                let when = &t.when;
+                let predator = senses.predator_distance.unwrap_or(f32::INFINITY);
+                when.min_neighbors.is_none_or(|n| senses.neighbors >= n)
+                    && when.max_neighbors.is_none_or(|n| senses.neighbors <= n)
+                    && when.predator_within.is_none_or(|d| predator < d)
+                    && when.predator_beyond.is_none_or(|d| predator >= d)
+                    && when.min_energy.is_none_or(|e| boid.energy >= e)
+                    && when.max_energy.is_none_or(|e| boid.energy <= e)
+                    && when.min_steps.is_none_or(|s| boid.behavior_steps >= s)
+            })
+            .map(|t| t.to)
+    }
+}
+
+/// Returns the distance from a point to the closest predator (moving obstacle)
+/// and the direction away from it.
+///
+/// # Arguments
+///
+/// * 'p' - The point.
+/// * 'obstacles' - The obstacles of the simulation.
+///
+/// # Returns
+///
+/// * 'Option<(f32, Vec2)>' - The distance and escape direction, or None without predators.
+pub fn closest_predator(p: Vec2, obstacles: &[Obstacle]) -> Option<(f32, Vec2)> {
+    obstacles
+        .iter()
+        .filter(|o| o.vel.length_squared() >= 1e-12) // Static obstacles are not predators
+        .map(|o| {
+            let away = (p - o.shape.center()).try_normalize().unwrap_or(Vec2::X);
+            (o.shape.signed_distance(p), away)
+        })
+        .min_by(|a, b| a.0.total_cmp(&b.0))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn machine() -> StateMachine {
+        let config: BehaviorConfig = serde_yaml::from_str(
+            "transitions:
+  - { from: flocking, to: fleeing, when: { predator_within: 50.0 } }
+  - { to: resting, when: { max_neighbors: 0 } }
+  - { from: fleeing, to: flocking, when: { predator_beyond: 100.0, min_steps: 10 } }",
+        )
+        .unwrap();
+        StateMachine::new(&config)
+    }
+
+    fn boid(state: BehaviorState, steps: u64) -> Boid {
+        Boid {
+            behavior: state,
+            behavior_steps: steps,
+            ..Boid::at(Vec2::ZERO, Vec2::X)
+        }
+    }
+
+    fn senses(neighbors: usize, predator_distance: Option<f32>) -> Senses {
+        Senses {
+            neighbors,
+            predator_distance,
+        }
+    }
+
+    #[test]
+    fn first_matching_transition_wins() {
+        let machine = machine();
+        let flocking = boid(BehaviorState::Flocking, 0);
+        // Both the first and the second transitions hold
+        assert_eq!(
+            machine.next_state(&flocking, &senses(0, Some(30.0))),
+            Some(BehaviorState::Fleeing)
+        );
+        assert_eq!(
+            machine.next_state(&flocking, &senses(0, None)),
+            Some(BehaviorState::Resting)
+        );
+        assert_eq!(machine.next_state(&flocking, &senses(5, Some(60.0))), None);
+    }
+
+    #[test]
+    fn every_condition_must_hold() {
+        let machine = machine();
+        let far = senses(3, Some(200.0));
+        assert_eq!(
+            machine.next_state(&boid(BehaviorState::Fleeing, 5), &far),
+            None
+        );
+        assert_eq!(
+            machine.next_state(&boid(BehaviorState::Fleeing, 10), &far),
+            Some(BehaviorState::Flocking)
+        );
+    }
+
+    #[test]
+    fn transitions_to_the_current_state_are_skipped() {
+        let machine = machine();
+        let resting = boid(BehaviorState::Resting, 0);
+        assert_eq!(machine.next_state(&resting, &senses(0, None)), None);
+    }
+}
@@ -7,7 +7,11 @@ use rand::Rng;
 use uuid::Uuid;
 
 use crate::color_utils::dir_to_color;
-use crate::config::BoidsConfig;
+use crate::config::{AvoidanceConfig, BehaviorState, BoidsConfig, EvolutionConfig, LifeConfig};
+use crate::food::FoodSource;
+use crate::genetics::Genes;
+use crate::roost::RoostState;
+use crate::startle::AlarmState;
 
 // --- Boid Struct Definition ---
 
@@ -16,6 +20,16 @@ pub struct Boid {
     pub id: Uuid,
     pub pos: Vec2,
     pub vel: Vec2,
+    pub energy: f32,             // only meaningful when the life cycle is enabled
+    pub genes: Genes,            // heritable flocking parameters
+    pub age: u64,                // steps survived
+    pub energy_gathered: f32,    // total energy taken from food sources
+    pub predator_clearance: f32, // steps spent clear of the predators (evolution only)
+    pub alarm: AlarmState,       // startle state (only used when startle is enabled)
+    pub roost: RoostState,       // roost assignment (only used when roosting is enabled)
+    pub behavior: BehaviorState, // behavioural state (only used with the state machine)
+    pub behavior_steps: u64,     // steps spent in the current behavioural state
+    pub group: Option<usize>,    // family group (only used when groups are enabled)
 }
 
 // --- Boid Implementation ---
@@ -26,12 +40,13 @@ impl Boid {
     /// # Arguments
     ///
     /// * 'pos' - The initial position vector
+    /// * 'genes' - The boid's flocking parameters
     /// * 'rng' - A mutable reference to a random number generator
     ///
     /// # Returns
     ///
     /// * 'Self' - A new Boid instance.
-    pub fn new(pos: Vec2, rng: &mut impl Rng) -> Self {
+    pub fn new(pos: Vec2, genes: Genes, rng: &mut impl Rng) -> Self {
         // Generate random initial velocity components between -1.0 and 1.0
         let angle = rng.random::<f32>() * 2.0 * std::f32::consts::PI;
         let vel = Vec2::new(angle.cos(), angle.sin()); // Start with normalized velocity
@@ -40,9 +55,37 @@ impl Boid {
             id: Uuid::new_v4(),
             pos,
             vel,
+            energy: 0.0,
+            genes,
+            age: 0,
+            energy_gathered: 0.0,
+            predator_clearance: 0.0,
+            alarm: AlarmState::default(),
+            roost: RoostState::default(),
+            behavior: BehaviorState::default(),
+            behavior_steps: 0,
+            group: None,
         }
     }
 
+    /// Creates a Boid with default genes at a position and velocity, for the tests.
+    ///
+    /// # Arguments
+    ///
+    /// * 'pos' - The position vector
+    /// * 'vel' - The velocity vector
+    #[cfg(test)]
+    pub fn at(pos: Vec2, vel: Vec2) -> Self {
+        use rand::SeedableRng;
+        let mut boid = Boid::new(
+            pos,
+            Genes::default(),
+            &mut rand::rngs::StdRng::seed_from_u64(0),
+        );
+        boid.vel = vel;
+        boid
+    }
+
     /// Calculates the boid's color based on its current velocity.
     ///
     /// # Argumennts
@@ -67,12 +110,15 @@ impl Boid {
 
     /// Calculates the necessary velocity adjustments based on neighbors and environment.
     /// This implements the core Boids rules: Separation, Alignemnt, Cohesion, and Boundary Avoidance.
+    /// The rule factors and ranges come from the given genes: the boid's own, possibly
+    /// overridden by the region it is in.
     /// Note: Thsi function *calculates* the change but does not apply it directly.
     ///
     /// # Arguments
     ///
     /// * 'neighbors' - A slice of reference to neighboring Boids within the visible range
     /// * 'config' - A reference to the BoidConfig parameters.
+    /// * 'genes' - A reference to the flocking parameters to use.
     /// * 'screen_dims' - A tuple containing the screen width and height.
     ///
     /// # Returns
@@ -82,6 +128,7 @@ impl Boid {
         &self,
         neighbors: &[&Boid],
         config: &BoidsConfig,
+        genes: &Genes,
         screen_dims: (f32, f32),
     ) -> Vec2 {
         let mut delta_v = Vec2::ZERO; // Initialize velocity to zero vector
@@ -91,9 +138,17 @@ impl Boid {
         let mut avg_pos = Vec2::ZERO; // Average position of neighbors ( for cohesion)
         let mut avg_vel = Vec2::ZERO; // Average velocity of neighbors ( for alignment)
         let mut neighbor_count = 0; // Count of neighbors within visible tange
+        let mut weight_sum = 0.0; // Sum of the neighbor weights (neighbor_count without groups)
+
+        // Weight of a neighbor in cohesion and alignment: with groups, mates and others differ
+        let weight_of = |other: &Boid| match (&config.groups, self.group) {
+            (Some(groups), Some(group)) if other.group == Some(group) => groups.mate_weight,
+            (Some(groups), Some(_)) => groups.other_weight,
+            _ => 1.0,
+        };
 
         // Precompute squared distances for efficiency
-        let protected_range_sq = config.protected_range * config.protected_range;
+        let protected_range_sq = genes.protected_range * genes.protected_range;
 
         for other in neighbors {
             let diff = self.pos - other.pos; // Vector from neighbors to self
@@ -109,27 +164,34 @@ impl Boid {
             }
 
             // --- Cohesion & Alignment Data Accumulation ---
-            avg_pos += other.pos; // Sum neighbor positions
-            avg_vel += other.vel; // Sum neighbor velocities
+            let weight = weight_of(other); // 1.0 unless groups are enabled
+            avg_pos += other.pos * weight; // Sum (weighted) neighbor positions
+            avg_vel += other.vel * weight; // Sum (weighted) neighbor velocities
+            weight_sum += weight;
             neighbor_count += 1;
         }
 
         // --- Apply separation force ---
         // Scale the accumulated separation vector by the avoidfactor
-        delta_v += close_dv * config.avoidfactor;
+        // Other avoidance modes replace the separation rule
+        if let AvoidanceConfig::Separation = config.avoidance {
+            delta_v += close_dv * genes.avoidfactor;
+        }
 
         // --- Rule 2 & 3: Alignment and Cohesion ( if neighbor exist ) ---
         if neighbor_count > 0 {
             let inv_neighbor_count = 1.0 / neighbor_count as f32;
 
             // --- Cohesion ---
-            // Calculate the center of mass of neighbors
+            // Calculate the center of mass of neighbors (with groups, the mean of the
+            // weighted offsets: self.pos scales with the mean weight, 1.0 without groups)
             avg_pos *= inv_neighbor_count;
-            let cohesion_dv = (avg_pos - self.pos) * config.centeringfactor;
+            let mean_weight = weight_sum / neighbor_count as f32;
+            let cohesion_dv = (avg_pos - self.pos * mean_weight) * genes.centeringfactor;
             delta_v += cohesion_dv;
 
-            // Calculate vector to match the average velocity
-            let alignment_dv = (avg_vel - self.vel) * config.matchingfactor;
+            // Calculate vector to match the (weighted) sum of neighbor velocities
+            let alignment_dv = (avg_vel - self.vel) * genes.matchingfactor;
             delta_v += alignment_dv; // Add alignamnet force
         }
 
@@ -182,5 +244,114 @@ impl Boid {
         // --- Update Position ---
         // Move the boid based on its final velocity and delta time (dt)
         self.pos += self.vel * config.dt;
+        self.age += 1;
+    }
+
+    /// Sets the boid's velocity to a collision-free one (ORCA) and updates its position.
+    /// The max speed is a constraint of the velocity computation, the min speed is not
+    /// enforced as rescaling the velocity could break the collision-free guarantee.
+    ///
+    /// # Arguments
+    ///
+    /// * 'vel' - The new velocity.
+    /// * 'config' - A reference to the BoidConfig parameter
+    pub fn apply_velocity(&mut self, vel: Vec2, config: &BoidsConfig) {
+        self.vel = vel;
+        self.pos += self.vel * config.dt;
+        self.age += 1;
+    }
+
+    /// Drains the boid's energy according to its speed and feeds it from the food sources it is in.
+    ///
+    /// # Arguments
+    ///
+    /// * 'life' - A reference to the LifeConfig parameters.
+    /// * 'food' - A slice of all the food sources of the simulation.
+    pub fn update_energy(&mut self, life: &LifeConfig, food: &[FoodSource]) {
+        let drain = life.base_drain + life.speed_drain * self.vel.length();
+        let gain = self.gather_food(food);
+        self.energy = (self.energy - drain + gain).min(life.max_energy);
+    }
+
+    /// Takes energy from the food sources the boid is in and adds it to its gathered total.
+    ///
+    /// # Arguments
+    ///
+    /// * 'food' - A slice of all the food sources of the simulation.
+    ///
+    /// # Returns
+    ///
+    /// * 'f32' - The energy gathered during this step.
+    pub fn gather_food(&mut self, food: &[FoodSource]) -> f32 {
+        let gain: f32 = food.iter().map(|source| source.energy_at(self.pos)).sum();
+        self.energy_gathered += gain;
+        gain
+    }
+
+    /// Returns the boid's fitness: steps survived plus the weighted gathered energy
+    /// and the weighted steps spent clear of the predators.
+    ///
+    /// # Arguments
+    ///
+    /// * 'evolution' - A reference to the EvolutionConfig parameters.
+    pub fn fitness(&self, evolution: &EvolutionConfig) -> f32 {
+        self.age as f32
+            + evolution.energy_weight * self.energy_gathered
+            + evolution.predator_weight * self.predator_clearance
+    }
+
+    /// Returns true while the boid has energy left.
+    pub fn is_alive(&self) -> bool {
+        self.energy > 0.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(groups: &str) -> BoidsConfig {
+        serde_yaml::from_str(&format!(
+            "{{ protected_range: 1.0, visible_range: 50.0, avoidfactor: 1.0, matchingfactor: 0.1,
+               centeringfactor: 0.01, turnfactor: 1.0, margin: 10.0, maxspeed: 5.0,
+               minspeed: 0.1, dt: 1.0, scale: false, trails_len: 0.5 {} }}",
+            groups
+        ))
+        .unwrap()
+    }
+
+    fn boid(pos: Vec2, vel: Vec2, group: Option<usize>) -> Boid {
+        Boid {
+            group,
+            ..Boid::at(pos, vel)
+        }
+    }
+
+    #[test]
+    fn alignment_sums_neighbor_velocities_without_groups() {
+        let config = config("");
+        let genes = Genes::from_config(&config);
+        let me = boid(Vec2::new(100.0, 100.0), Vec2::new(1.0, 0.0), None);
+        let a = boid(Vec2::new(110.0, 100.0), Vec2::new(0.0, 1.0), None);
+        let b = boid(Vec2::new(100.0, 120.0), Vec2::new(0.0, 2.0), None);
+        let dv = me.calculate_velocity_change(&[&a, &b], &config, &genes, (1000.0, 1000.0));
+
+        let alignment = (Vec2::new(0.0, 3.0) - me.vel) * 0.1;
+        let cohesion = (Vec2::new(105.0, 110.0) - me.pos) * 0.01;
+        assert_eq!(dv, alignment + cohesion);
+    }
+
+    #[test]
+    fn groups_weight_mates_and_others() {
+        let config = config(", groups: { mate_weight: 2.0, other_weight: 0.5 }");
+        let genes = Genes::from_config(&config);
+        let me = boid(Vec2::new(100.0, 100.0), Vec2::new(1.0, 0.0), Some(0));
+        let mate = boid(Vec2::new(110.0, 100.0), Vec2::new(0.0, 1.0), Some(0));
+        let other = boid(Vec2::new(100.0, 120.0), Vec2::new(0.0, 2.0), Some(1));
+        let dv = me.calculate_velocity_change(&[&mate, &other], &config, &genes, (1000.0, 1000.0));
+
+        let alignment = (Vec2::new(0.0, 2.0 * 1.0 + 0.5 * 2.0) - me.vel) * 0.1;
+        let cohesion = (Vec2::new(2.0 * 10.0, 0.5 * 20.0) / 2.0) * 0.01;
+        assert!((dv - (alignment + cohesion)).length() < 1e-5);
     }
 }
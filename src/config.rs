@@ -13,6 +13,13 @@ pub struct Resolution {
     pub y: f32,
 }
 
+// A point in world coordinates
+#[derive(Deserialize, Debug, Clone, Copy)]
+pub struct Point {
+    pub x: f32,
+    pub y: f32,
+}
+
 // init window position
 #[derive(Deserialize, Debug, Clone, Copy)]
 pub struct Position {
@@ -35,15 +42,839 @@ pub struct BoidsConfig {
     pub dt: f32,
     pub scale: bool,
     pub trails_len: f32,
+    pub life: Option<LifeConfig>, // optional energy, death and reproduction
+    pub evolution: Option<EvolutionConfig>, // optional evolution of per-boid parameters
+    pub collision: Option<CollisionConfig>, // optional hard-sphere collision resolution
+    #[serde(default)]
+    pub avoidance: AvoidanceConfig, // rule used to avoid neighbors (separation by default)
+    #[serde(default)]
+    pub interaction: InteractionConfig, // how boids pick and follow neighbors (metric by default)
+    #[serde(default)]
+    pub obstacle_avoidance: ObstacleAvoidanceConfig, // how boids steer around obstacles
+    #[serde(default)]
+    pub obstacle_occlusion: bool, // neighbors hidden behind an obstacle are ignored
+    pub foraging: Option<ForagingConfig>, // optional steering towards the food sources
+    pub pheromone: Option<PheromoneConfig>, // optional pheromone field (stigmergy)
+    pub startle: Option<StartleConfig>, // optional alarm spreading between neighbors
+    pub formation: Option<FormationConfig>, // optional formation flight behind leaders
+    pub roosting: Option<RoostingConfig>, // optional day cycle with homing to the roosts
+    pub perception: Option<PerceptionConfig>, // optional sensory delay and perception noise
+    pub groups: Option<GroupsConfig>, // optional family groups inside the flocks
 }
 
-// The top-level config struct
+impl BoidsConfig {
+    /// Names of the top-level numeric parameters that can be changed during a run
+    /// (the numeric fields of the sections are named by dotted paths).
+    pub const PARAM_NAMES: [&'static str; 11] = [
+        "protected_range",
+        "visible_range",
+        "avoidfactor",
+        "matchingfactor",
+        "centeringfactor",
+        "turnfactor",
+        "margin",
+        "maxspeed",
+        "minspeed",
+        "dt",
+        "trails_len",
+    ];
+
+    /// Resolves a numeric parameter from its name: a top-level field such as
+    /// matchingfactor, or a dotted path to a field of a section such as
+    /// avoidance.time_horizon.
+    ///
+    /// # Arguments
+    ///
+    /// * 'name' - The name or dotted path of the parameter, as written in boids.yaml.
+    ///
+    /// # Returns
+    ///
+    /// * Result<Param, String> - The parameter, or an error if there is no numeric
+    ///   parameter of that name or its section is not enabled.
+    pub fn param(&self, name: &str) -> Result<Param, String> {
+        use Param::{F32, U32, U64, Usize};
+        let param = match name {
+            "protected_range" => F32(|c| Some(&mut c.protected_range)),
+            "visible_range" => F32(|c| Some(&mut c.visible_range)),
+            "avoidfactor" => F32(|c| Some(&mut c.avoidfactor)),
+            "matchingfactor" => F32(|c| Some(&mut c.matchingfactor)),
+            "centeringfactor" => F32(|c| Some(&mut c.centeringfactor)),
+            "turnfactor" => F32(|c| Some(&mut c.turnfactor)),
+            "margin" => F32(|c| Some(&mut c.margin)),
+            "maxspeed" => F32(|c| Some(&mut c.maxspeed)),
+            "minspeed" => F32(|c| Some(&mut c.minspeed)),
+            "dt" => F32(|c| Some(&mut c.dt)),
+            "trails_len" => F32(|c| Some(&mut c.trails_len)),
+            // Life cycle
+            "life.initial_energy" => F32(|c| c.life.as_mut().map(|s| &mut s.initial_energy)),
+            "life.max_energy" => F32(|c| c.life.as_mut().map(|s| &mut s.max_energy)),
+            "life.base_drain" => F32(|c| c.life.as_mut().map(|s| &mut s.base_drain)),
+            "life.speed_drain" => F32(|c| c.life.as_mut().map(|s| &mut s.speed_drain)),
+            "life.reproduction_threshold" => {
+                F32(|c| c.life.as_mut().map(|s| &mut s.reproduction_threshold))
+            }
+            "life.offspring_energy" => F32(|c| c.life.as_mut().map(|s| &mut s.offspring_energy)),
+            "life.offspring_spread" => F32(|c| c.life.as_mut().map(|s| &mut s.offspring_spread)),
+            "life.max_population" => Usize(|c| c.life.as_mut().map(|s| &mut s.max_population)),
+            // Evolution
+            "evolution.mutation_sigma" => {
+                F32(|c| c.evolution.as_mut().map(|s| &mut s.mutation_sigma))
+            }
+            "evolution.generation_steps" => {
+                U64(|c| c.evolution.as_mut().map(|s| &mut s.generation_steps))
+            }
+            "evolution.tournament_size" => {
+                Usize(|c| c.evolution.as_mut().map(|s| &mut s.tournament_size))
+            }
+            "evolution.energy_weight" => {
+                F32(|c| c.evolution.as_mut().map(|s| &mut s.energy_weight))
+            }
+            "evolution.predator_weight" => {
+                F32(|c| c.evolution.as_mut().map(|s| &mut s.predator_weight))
+            }
+            "evolution.catch_distance" => {
+                F32(|c| c.evolution.as_mut().and_then(|s| s.catch_distance.as_mut()))
+            }
+            "evolution.offspring_spread" => F32(|c| {
+                c.evolution
+                    .as_mut()
+                    .and_then(|s| s.offspring_spread.as_mut())
+            }),
+            // Collisions
+            "collision.body_radius" => F32(|c| c.collision.as_mut().map(|s| &mut s.body_radius)),
+            "collision.iterations" => U32(|c| c.collision.as_mut().map(|s| &mut s.iterations)),
+            // Neighbor avoidance (fields of the selected mode)
+            "avoidance.radius" => F32(|c| match &mut c.avoidance {
+                AvoidanceConfig::Orca { radius, .. } => Some(radius),
+                AvoidanceConfig::TimeToCollision { radius, .. } => Some(radius),
+                AvoidanceConfig::Separation => None,
+            }),
+            "avoidance.time_horizon" => F32(|c| match &mut c.avoidance {
+                AvoidanceConfig::Orca { time_horizon, .. } => Some(time_horizon),
+                _ => None,
+            }),
+            "avoidance.horizon" => F32(|c| match &mut c.avoidance {
+                AvoidanceConfig::TimeToCollision { horizon, .. } => Some(horizon),
+                _ => None,
+            }),
+            "avoidance.strength" => F32(|c| match &mut c.avoidance {
+                AvoidanceConfig::TimeToCollision { strength, .. } => Some(strength),
+                _ => None,
+            }),
+            // Interaction model (fields of the selected model)
+            "interaction.body_radius" => F32(|c| match &mut c.interaction {
+                InteractionConfig::Projection { body_radius, .. } => Some(body_radius),
+                InteractionConfig::Metric => None,
+            }),
+            "interaction.range" => F32(|c| match &mut c.interaction {
+                InteractionConfig::Projection { range, .. } => Some(range),
+                InteractionConfig::Metric => None,
+            }),
+            "interaction.projection_factor" => F32(|c| match &mut c.interaction {
+                InteractionConfig::Projection {
+                    projection_factor, ..
+                } => Some(projection_factor),
+                InteractionConfig::Metric => None,
+            }),
+            "interaction.alignment_factor" => F32(|c| match &mut c.interaction {
+                InteractionConfig::Projection {
+                    alignment_factor, ..
+                } => Some(alignment_factor),
+                InteractionConfig::Metric => None,
+            }),
+            // Obstacle avoidance
+            "obstacle_avoidance.distance" => F32(|c| Some(&mut c.obstacle_avoidance.distance)),
+            "obstacle_avoidance.factor" => F32(|c| Some(&mut c.obstacle_avoidance.factor)),
+            "obstacle_avoidance.lookahead" => F32(|c| Some(&mut c.obstacle_avoidance.lookahead)),
+            "obstacle_avoidance.raycast.rays" => {
+                Usize(|c| c.obstacle_avoidance.raycast.as_mut().map(|s| &mut s.rays))
+            }
+            "obstacle_avoidance.raycast.fov" => {
+                F32(|c| c.obstacle_avoidance.raycast.as_mut().map(|s| &mut s.fov))
+            }
+            "obstacle_avoidance.raycast.length" => {
+                F32(|c| c.obstacle_avoidance.raycast.as_mut().map(|s| &mut s.length))
+            }
+            "obstacle_avoidance.raycast.strength" => F32(|c| {
+                c.obstacle_avoidance
+                    .raycast
+                    .as_mut()
+                    .map(|s| &mut s.strength)
+            }),
+            // Foraging
+            "foraging.sensing_range" => F32(|c| c.foraging.as_mut().map(|s| &mut s.sensing_range)),
+            "foraging.factor" => F32(|c| c.foraging.as_mut().map(|s| &mut s.factor)),
+            // Pheromone field
+            "pheromone.cell_size" => F32(|c| c.pheromone.as_mut().map(|s| &mut s.cell_size)),
+            "pheromone.deposit" => F32(|c| c.pheromone.as_mut().map(|s| &mut s.deposit)),
+            "pheromone.diffusion" => F32(|c| c.pheromone.as_mut().map(|s| &mut s.diffusion)),
+            "pheromone.evaporation" => F32(|c| c.pheromone.as_mut().map(|s| &mut s.evaporation)),
+            "pheromone.factor" => F32(|c| c.pheromone.as_mut().map(|s| &mut s.factor)),
+            "pheromone.sensitivity" => F32(|c| c.pheromone.as_mut().map(|s| &mut s.sensitivity)),
+            // Startle cascades
+            "startle.probability" => F32(|c| c.startle.as_mut().map(|s| &mut s.probability)),
+            "startle.delay" => U32(|c| c.startle.as_mut().map(|s| &mut s.delay)),
+            "startle.duration" => U32(|c| c.startle.as_mut().map(|s| &mut s.duration)),
+            "startle.refractory" => U32(|c| c.startle.as_mut().map(|s| &mut s.refractory)),
+            "startle.turn_angle" => F32(|c| c.startle.as_mut().map(|s| &mut s.turn_angle)),
+            "startle.speed_boost" => F32(|c| c.startle.as_mut().map(|s| &mut s.speed_boost)),
+            "startle.random_rate" => F32(|c| c.startle.as_mut().map(|s| &mut s.random_rate)),
+            "startle.predator_distance" => F32(|c| {
+                c.startle
+                    .as_mut()
+                    .and_then(|s| s.predator_distance.as_mut())
+            }),
+            // Formation flight
+            "formation.size" => Usize(|c| c.formation.as_mut().map(|s| &mut s.size)),
+            "formation.spacing" => F32(|c| c.formation.as_mut().map(|s| &mut s.spacing)),
+            "formation.spring" => F32(|c| c.formation.as_mut().map(|s| &mut s.spring)),
+            "formation.damping" => F32(|c| c.formation.as_mut().map(|s| &mut s.damping)),
+            "formation.break_distance" => {
+                F32(|c| c.formation.as_mut().map(|s| &mut s.break_distance))
+            }
+            "formation.shape.angle" => F32(|c| match c.formation.as_mut().map(|s| &mut s.shape) {
+                Some(FormationShape::V { angle } | FormationShape::Echelon { angle }) => {
+                    Some(angle)
+                }
+                _ => None,
+            }),
+            "formation.shape.columns" => {
+                U32(|c| match c.formation.as_mut().map(|s| &mut s.shape) {
+                    Some(FormationShape::Grid { columns }) => Some(columns),
+                    _ => None,
+                })
+            }
+            // Roosting day cycle
+            "roosting.day_length" => U64(|c| c.roosting.as_mut().map(|s| &mut s.day_length)),
+            "roosting.homing_start" => F32(|c| c.roosting.as_mut().map(|s| &mut s.homing_start)),
+            "roosting.dusk" => F32(|c| c.roosting.as_mut().map(|s| &mut s.dusk)),
+            "roosting.homing_factor" => F32(|c| c.roosting.as_mut().map(|s| &mut s.homing_factor)),
+            "roosting.approach_distance" => {
+                F32(|c| c.roosting.as_mut().map(|s| &mut s.approach_distance))
+            }
+            "roosting.landing_speed" => F32(|c| c.roosting.as_mut().map(|s| &mut s.landing_speed)),
+            "roosting.settle_factor" => F32(|c| c.roosting.as_mut().map(|s| &mut s.settle_factor)),
+            // Perception
+            "perception.delay" => Usize(|c| c.perception.as_mut().map(|s| &mut s.delay)),
+            "perception.position_noise" => {
+                F32(|c| c.perception.as_mut().map(|s| &mut s.position_noise))
+            }
+            "perception.heading_noise" => {
+                F32(|c| c.perception.as_mut().map(|s| &mut s.heading_noise))
+            }
+            // Family groups
+            "groups.mate_weight" => F32(|c| c.groups.as_mut().map(|s| &mut s.mate_weight)),
+            "groups.other_weight" => F32(|c| c.groups.as_mut().map(|s| &mut s.other_weight)),
+            "groups.spawn_size" => Usize(|c| c.groups.as_mut().map(|s| &mut s.spawn_size)),
+            "groups.join_distance" => {
+                F32(|c| c.groups.as_mut().and_then(|s| s.join_distance.as_mut()))
+            }
+            "groups.max_size" => Usize(|c| c.groups.as_mut().and_then(|s| s.max_size.as_mut())),
+            "groups.leave_distance" => {
+                F32(|c| c.groups.as_mut().and_then(|s| s.leave_distance.as_mut()))
+            }
+            _ => {
+                return Err(format!(
+                    "unknown parameter '{}' (expected one of {}, or a dotted path to a \
+                     numeric field of a section, e.g. avoidance.time_horizon)",
+                    name,
+                    Self::PARAM_NAMES.join(", ")
+                ));
+            }
+        };
+        if !param.is_enabled(self) {
+            return Err(format!(
+                "parameter '{}' is not set: its section (or mode) is not enabled",
+                name
+            ));
+        }
+        Ok(param)
+    }
+}
+
+// A numeric parameter of BoidsConfig, by its type and the access to its field (None
+// when its section or mode is not enabled)
+#[derive(Debug, Clone, Copy)]
+pub enum Param {
+    F32(fn(&mut BoidsConfig) -> Option<&mut f32>),
+    U32(fn(&mut BoidsConfig) -> Option<&mut u32>),
+    U64(fn(&mut BoidsConfig) -> Option<&mut u64>),
+    Usize(fn(&mut BoidsConfig) -> Option<&mut usize>),
+}
+
+// Time schedule of a numeric boids_config parameter, written in place of its value
+#[derive(Deserialize, Debug, Clone)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ScheduleConfig {
+    // Piecewise-linear between [step, value] keyframes, held before the first and after the last
+    Keyframes {
+        keys: Vec<(u64, f32)>,
+        #[serde(default)]
+        repeat: bool, // start over from the first keyframe after the last one
+    },
+    // Sinusoidal oscillation around `mean`, of `period` steps, starting at `phase` degrees
+    Sine {
+        mean: f32,
+        amplitude: f32,
+        period: f32,
+        #[serde(default)]
+        phase: f32,
+    },
+}
+
+// Group params: boids weight their group mates differently from the other neighbors
+#[derive(Deserialize, Debug, Clone, Copy)]
+pub struct GroupsConfig {
+    pub mate_weight: f32,  // cohesion and alignment weight of the group mates
+    pub other_weight: f32, // cohesion and alignment weight of the other neighbors
+    #[serde(default)]
+    pub spawn_size: usize, // boids per group formed at startup (0 = no groups at startup)
+    pub join_distance: Option<f32>, // ungrouped boids closer than this join or found groups
+    pub max_size: Option<usize>, // groups stop accepting boids at this size
+    pub leave_distance: Option<f32>, // members further than this from their group center leave
+}
+
+// Perception params: neighbors are seen as they were `delay` steps ago, blurred by noise
+#[derive(Deserialize, Debug, Clone, Copy)]
+pub struct PerceptionConfig {
+    #[serde(default)]
+    pub delay: usize, // steps between a neighbor's state and its perception
+    #[serde(default)]
+    pub position_noise: f32, // standard deviation of the perceived positions, in px
+    #[serde(default)]
+    pub heading_noise: f32, // standard deviation of the perceived headings, in degrees
+}
+
+// Roosting params (phases are fractions of the day, dawn being at 0)
+#[derive(Deserialize, Debug, Clone, Copy)]
+pub struct RoostingConfig {
+    pub day_length: u64,        // steps per day
+    pub homing_start: f32,      // phase at which the pull towards the roost starts growing
+    pub dusk: f32,              // phase at which the pull is full and boids may land
+    pub homing_factor: f32,     // full strength of the pull towards the roost
+    pub approach_distance: f32, // boids slow down within this distance of the roost edge at night
+    pub landing_speed: f32,     // speed limit when reaching the roost
+    pub settle_factor: f32,     // fraction of the velocity a landed boid loses per step
+}
+
+// Formation flight params
+#[derive(Deserialize, Debug, Clone, Copy)]
+pub struct FormationConfig {
+    pub shape: FormationShape,
+    pub size: usize,         // boids per formation, leader included
+    pub spacing: f32,        // distance between neighboring slots
+    pub spring: f32,         // pull towards the slot, per px of error
+    pub damping: f32,        // pull towards the leader's velocity
+    pub break_distance: f32, // members further than this from their slot leave the formation
+}
+
+// Shape of the formations, selected with the `type` key (angles in degrees from the
+// leader's heading, backwards)
+#[derive(Deserialize, Debug, Clone, Copy)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum FormationShape {
+    V { angle: f32 },
+    Echelon { angle: f32 },
+    Line,
+    Grid { columns: u32 },
+}
+
+// Startle cascade params
+#[derive(Deserialize, Debug, Clone, Copy)]
+pub struct StartleConfig {
+    pub probability: f32, // chance per step of catching the alarm from each alarmed neighbor
+    pub delay: u32,       // steps between catching the alarm and reacting
+    pub duration: u32,    // steps an alarmed boid keeps escaping
+    pub refractory: u32,  // steps after an alarm during which a boid cannot be alarmed again
+    pub turn_angle: f32,  // angle of the escape turn, in degrees
+    pub speed_boost: f32, // maxspeed multiplier while escaping
+    #[serde(default)]
+    pub random_rate: f32, // chance per boid per step of a spontaneous alarm
+    pub predator_distance: Option<f32>, // moving obstacles closer than this alarm boids
+}
+
+// Pheromone field params
+#[derive(Deserialize, Debug, Clone, Copy)]
+pub struct PheromoneConfig {
+    pub cell_size: f32,   // side of a grid cell
+    pub deposit: f32,     // pheromone added by each boid per step in its cell
+    pub diffusion: f32,   // fraction of a cell's value exchanged with its neighbors per step
+    pub evaporation: f32, // fraction of the pheromone lost per step
+    pub factor: f32,      // strength of the steering up the gradient
+    pub sensitivity: f32, // weakest gradient boids react to
+}
+
+// Foraging params
+#[derive(Deserialize, Debug, Clone, Copy)]
+pub struct ForagingConfig {
+    pub sensing_range: f32, // distance from a food source's edge at which boids detect it
+    pub factor: f32,        // strength of the steering towards the closest detected source
+}
+
+// Obstacle avoidance params
+#[derive(Deserialize, Debug, Clone, Copy)]
+pub struct ObstacleAvoidanceConfig {
+    pub distance: f32,  // distance from an obstacle where boids start turning away
+    pub factor: f32,    // avoidance force at the obstacle boundary
+    pub lookahead: f32, // steps ahead the motion relative to moving obstacles is predicted
+    pub raycast: Option<RaycastConfig>, // steer with a fan of rays instead of the radial push
+}
+
+// Raycast vision params
 #[derive(Deserialize, Debug, Clone, Copy)]
+pub struct RaycastConfig {
+    pub rays: usize,   // number of rays in the fan
+    pub fov: f32,      // angle covered by the fan, in degrees, centered on the heading
+    pub length: f32,   // length of the rays
+    pub strength: f32, // how fast the velocity turns towards the clear direction
+}
+
+impl Default for ObstacleAvoidanceConfig {
+    fn default() -> Self {
+        ObstacleAvoidanceConfig {
+            distance: 20.0,
+            factor: 1.0,
+            lookahead: 10.0,
+            raycast: None,
+        }
+    }
+}
+
+// Neighbor avoidance rule, selected with the `mode` key
+#[derive(Deserialize, Debug, Clone, Copy, Default)]
+#[serde(tag = "mode", rename_all = "snake_case")]
+pub enum AvoidanceConfig {
+    // Soft 1/d push inside protected_range (classic boids)
+    #[default]
+    Separation,
+    // Reciprocal velocity obstacles: collision-free velocity closest to the flocking one
+    Orca {
+        radius: f32,       // body radius of every boid
+        time_horizon: f32, // collisions within this many steps are avoided
+    },
+    // Anticipatory steering against the collisions predicted within a horizon
+    TimeToCollision {
+        radius: f32,   // body radius of every boid
+        horizon: f32,  // collisions further than this many steps are ignored
+        strength: f32, // velocity change for a collision happening now
+    },
+}
+
+// Interaction model, selected with the `model` key
+#[derive(Deserialize, Debug, Clone, Copy, Default)]
+#[serde(tag = "model", rename_all = "snake_case")]
+pub enum InteractionConfig {
+    // Cohesion and alignment with every neighbor within visible_range (classic boids)
+    #[default]
+    Metric,
+    // Steering towards the edges of the visual projection of the other boids, and
+    // alignment with the boids not hidden behind closer ones (Pearce et al. 2014)
+    Projection {
+        body_radius: f32,       // radius of the disc every boid appears as
+        range: f32,             // boids further than this are ignored
+        projection_factor: f32, // strength of the steering towards the projection edges
+        alignment_factor: f32,  // matching of the average velocity of the visible boids
+    },
+}
+
+// Life cycle params (energy, death and reproduction)
+#[derive(Deserialize, Debug, Clone, Copy)]
+pub struct LifeConfig {
+    pub initial_energy: f32,         // energy of the boids spawned at startup
+    pub max_energy: f32,             // energy cap
+    pub base_drain: f32,             // energy lost every step
+    pub speed_drain: f32,            // extra energy lost per step per unit of speed
+    pub reproduction_threshold: f32, // energy needed to reproduce
+    pub offspring_energy: f32,       // fraction of the parent energy given to the offspring
+    pub offspring_spread: f32,       // max distance between parent and offspring
+    pub max_population: usize,       // reproduction stops above this population
+}
+
+// Evolution params (genetic algorithm over the per-boid flocking parameters)
+#[derive(Deserialize, Debug, Clone, Copy)]
+pub struct EvolutionConfig {
+    pub mutation_sigma: f32, // relative standard deviation of the gene mutations
+    pub generation_steps: u64, // length of a generation in steps
+    pub generational: bool,  // replace the whole population at the end of each generation
+    pub tournament_size: usize, // number of candidates per tournament selection
+    pub energy_weight: f32,  // fitness weight of the gathered energy (steps survived weigh 1.0)
+    #[serde(default)]
+    pub predator_weight: f32, // fitness weight of the steps spent clear of the predators
+    pub catch_distance: Option<f32>, // predators (moving obstacles) kill the boids closer than this
+    pub offspring_spread: Option<f32>, // generational offspring land this close to their parent (protected_range if unset)
+}
+
+// Hard-sphere collision params (overlap resolution after integration)
+#[derive(Deserialize, Debug, Clone, Copy)]
+pub struct CollisionConfig {
+    pub body_radius: f32, // boids closer than twice this radius overlap
+    pub iterations: u32,  // number of position projection passes per step
+}
+
+// Food source replenishing the energy of boids inside its radius
+#[derive(Deserialize, Debug, Clone, Copy)]
+pub struct FoodSourceConfig {
+    pub x: f32,
+    pub y: f32,
+    pub radius: f32,
+    pub energy_rate: f32, // energy gained per step by each boid inside the radius
+    pub amount: Option<f32>, // food available (and maximum amount), unlimited if unset
+    #[serde(default)]
+    pub regrowth: f32, // food added back per step, up to the initial amount
+}
+
+// Shape of an obstacle, selected with the `type` key (x, y is the center)
+#[derive(Deserialize, Debug, Clone, Copy)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ShapeConfig {
+    Circle {
+        x: f32,
+        y: f32,
+        radius: f32,
+    },
+    Rectangle {
+        x: f32,
+        y: f32,
+        width: f32,
+        height: f32,
+    },
+}
+
+// Scripted motion of an obstacle, selected with the `type` key
+#[derive(Deserialize, Debug, Clone, Default)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum TrajectoryConfig {
+    #[default]
+    Static,
+    // Constant velocity, optionally wrapping around the screen edges
+    Linear {
+        vx: f32,
+        vy: f32,
+        #[serde(default)]
+        wrap: bool,
+    },
+    // Orbit around a center, through the starting position (angular speed in rad per step)
+    Circular {
+        center_x: f32,
+        center_y: f32,
+        angular_speed: f32,
+    },
+    // Straight lines through the waypoints at a constant speed (px per step)
+    Waypoints {
+        points: Vec<Point>,
+        speed: f32,
+        #[serde(default)]
+        looped: bool,
+    },
+}
+
+// An obstacle and its trajectory
+#[derive(Deserialize, Debug, Clone)]
+pub struct ObstacleConfig {
+    pub shape: ShapeConfig,
+    #[serde(default)]
+    pub trajectory: TrajectoryConfig,
+}
+
+// Local values replacing the BoidsConfig ones (unset values are kept)
+#[derive(Deserialize, Debug, Clone, Copy, Default)]
+pub struct ParamOverrides {
+    pub protected_range: Option<f32>,
+    pub visible_range: Option<f32>,
+    pub avoidfactor: Option<f32>,
+    pub matchingfactor: Option<f32>,
+    pub centeringfactor: Option<f32>,
+    pub turnfactor: Option<f32>,
+    pub maxspeed: Option<f32>,
+    pub minspeed: Option<f32>,
+}
+
+// A zone where the overrides apply, fading out over `blend` px outside its shape
+#[derive(Deserialize, Debug, Clone, Copy)]
+pub struct ZoneConfig {
+    pub shape: ShapeConfig,
+    #[serde(default)]
+    pub blend: f32, // width of the transition band outside the shape (0 = hard edge)
+    pub overrides: ParamOverrides,
+}
+
+// A map region: pixels of this colour apply the overrides
+#[derive(Deserialize, Debug, Clone, Copy)]
+pub struct MapRegionConfig {
+    pub color: [u8; 3], // RGB colour of the region in the map image
+    pub overrides: ParamOverrides,
+}
+
+// Bitmap world map (black walls, green spawn zones, red goals, coloured regions)
+#[derive(Deserialize, Debug, Clone)]
+pub struct MapConfig {
+    pub path: String,       // path of the PNG image
+    pub cell_size: f32,     // world size of one pixel
+    pub wall_distance: f32, // distance from a wall where boids start turning away
+    pub wall_factor: f32,   // wall avoidance force at the wall boundary
+    #[serde(default)]
+    pub goal_factor: f32, // attraction along the shortest path to the closest goal
+    #[serde(default)]
+    pub regions: Vec<MapRegionConfig>,
+}
+
+// Source of the target points of a morph stage, selected with the `type` key
+#[derive(Deserialize, Debug, Clone)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum MorphSourceConfig {
+    // Dark (or opaque on a transparent background) pixels of an image
+    Image { path: String },
+    // Outline of the paths of an SVG file (M, L, H, V, C, Q and Z commands)
+    Svg { path: String },
+    // Text rendered with the built-in 5x7 font
+    Text { text: String },
+}
+
+// One shape of the morph timeline, fitted to `width` around (x, y)
+#[derive(Deserialize, Debug, Clone)]
+pub struct MorphStageConfig {
+    pub source: MorphSourceConfig,
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub steps: u64, // steps before moving on to the next stage
+}
+
+// Shape morphing: boids fly to points sampled from a timeline of shapes
+#[derive(Deserialize, Debug, Clone)]
+pub struct MorphConfig {
+    pub stages: Vec<MorphStageConfig>,
+    pub factor: f32,           // how fast the velocity turns towards the target
+    pub slowing_distance: f32, // boids slow down within this distance of their target
+    #[serde(default)]
+    pub looped: bool, // start over after the last stage (otherwise hold it)
+}
+
+// A roost boids return to at night
+#[derive(Deserialize, Debug, Clone, Copy)]
+pub struct RoostConfig {
+    pub x: f32,
+    pub y: f32,
+    pub radius: f32, // boids land within this distance of the center
+}
+
+// An emitter spawning boids at a steady rate (through-flow)
+#[derive(Deserialize, Debug, Clone, Copy)]
+pub struct EmitterConfig {
+    pub x: f32,
+    pub y: f32,
+    pub radius: f32, // boids spawn at a random point within this distance of the center
+    pub rate: f32,   // boids spawned per step (fractions accumulate over the steps)
+    pub heading: f32, // mean initial heading, in degrees (0 = right, 90 = down)
+    #[serde(default)]
+    pub spread: f32, // initial headings vary uniformly by up to this, in degrees (180 = any)
+    pub speed: f32,  // initial speed
+    pub max_population: usize, // no boids are spawned while the population is at this size
+}
+
+// A sink removing the boids entering it (through-flow)
+#[derive(Deserialize, Debug, Clone, Copy)]
+pub struct SinkConfig {
+    pub x: f32,
+    pub y: f32,
+    pub radius: f32, // boids within this distance of the center are removed
+}
+
+// A Gaussian cluster of a clustered spawn
+#[derive(Deserialize, Debug, Clone, Copy)]
+pub struct ClusterConfig {
+    pub x: f32,
+    pub y: f32,
+    pub sigma: f32,          // standard deviation of the positions around the center
+    pub weight: Option<f32>, // share of the boids spawned in this cluster (1 if unset)
+}
+
+// Where the initial boids are placed, selected with the `type` key
+#[derive(Deserialize, Debug, Clone)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum SpawnPatternConfig {
+    // Uniform inside the world inset by `inset` of its size on each side (1/8 if unset)
+    Uniform {
+        inset: Option<f32>,
+    },
+    // Gaussian clusters
+    Clusters {
+        clusters: Vec<ClusterConfig>,
+    },
+    // Ring of `radius` around (x, y), positions jittered radially over `width`
+    Ring {
+        x: f32,
+        y: f32,
+        radius: f32,
+        #[serde(default)]
+        width: f32,
+    },
+    // Square lattice of `spacing` centred on (x, y), `columns` wide (square if unset)
+    Grid {
+        x: f32,
+        y: f32,
+        spacing: f32,
+        columns: Option<usize>,
+    },
+    // Dark (or opaque on a transparent background) pixels of an image scaled to `width` around (x, y)
+    Image {
+        path: String,
+        x: f32,
+        y: f32,
+        width: f32,
+    },
+}
+
+impl Default for SpawnPatternConfig {
+    fn default() -> Self {
+        SpawnPatternConfig::Uniform { inset: None }
+    }
+}
+
+// Initial velocities of the boids, selected with the `mode` key
+#[derive(Deserialize, Debug, Clone, Copy, Default)]
+#[serde(tag = "mode", rename_all = "snake_case")]
+pub enum SpawnVelocityConfig {
+    // Uniformly random headings at unit speed
+    #[default]
+    Random,
+    // Every boid heading at `heading` degrees (0 = right, 90 = down)
+    Aligned {
+        heading: f32,
+        speed: f32,
+    },
+    // Circling around (x, y), counterclockwise on screen unless `clockwise`
+    Vortex {
+        x: f32,
+        y: f32,
+        speed: f32,
+        #[serde(default)]
+        clockwise: bool,
+    },
+    // Heading away from (x, y)
+    Radial {
+        x: f32,
+        y: f32,
+        speed: f32,
+    },
+}
+
+// Initial placement and velocities of the boids
+#[derive(Deserialize, Debug, Clone, Default)]
+pub struct SpawnConfig {
+    #[serde(default)]
+    pub pattern: SpawnPatternConfig,
+    #[serde(default)]
+    pub velocity: SpawnVelocityConfig,
+}
+
+// Scenario file giving the exact initial state of every boid (replaces the spawning)
+#[derive(Deserialize, Debug, Clone)]
+pub struct ScenarioConfig {
+    pub path: String,      // path of the CSV or JSON scenario file
+    pub seed: Option<u64>, // seed of the simulator's random number generator (overrides the file's)
+}
+
+// Behavioural state of a boid
+#[derive(Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum BehaviorState {
+    #[default]
+    Flocking,
+    Fleeing,
+    Foraging,
+    Resting,
+}
+
+// Rule weights of a behavioural state (missing weights are 1, flee 0)
+#[derive(Deserialize, Debug, Clone, Copy)]
+#[serde(default)]
+pub struct StateWeights {
+    pub separation: f32, // avoidfactor multiplier
+    pub alignment: f32,  // matchingfactor multiplier
+    pub cohesion: f32,   // centeringfactor multiplier
+    pub speed: f32,      // maxspeed and minspeed multiplier
+    pub foraging: f32,   // foraging steering multiplier
+    pub flee: f32,       // strength of the steering away from the closest predator
+}
+
+impl Default for StateWeights {
+    fn default() -> Self {
+        StateWeights {
+            separation: 1.0,
+            alignment: 1.0,
+            cohesion: 1.0,
+            speed: 1.0,
+            foraging: 1.0,
+            flee: 0.0,
+        }
+    }
+}
+
+// Conditions of a transition, all of which must hold (unset ones are ignored)
+#[derive(Deserialize, Debug, Clone, Copy, Default)]
+pub struct TransitionCondition {
+    pub min_neighbors: Option<usize>, // at least this many neighbors in visible range
+    pub max_neighbors: Option<usize>, // at most this many neighbors in visible range
+    pub predator_within: Option<f32>, // a predator (moving obstacle) is closer than this
+    pub predator_beyond: Option<f32>, // no predator is closer than this
+    pub min_energy: Option<f32>,      // energy at least this (life cycle)
+    pub max_energy: Option<f32>,      // energy at most this (life cycle)
+    pub min_steps: Option<u64>,       // at least this many steps spent in the current state
+}
+
+// A transition between behavioural states
+#[derive(Deserialize, Debug, Clone, Copy)]
+pub struct TransitionConfig {
+    pub from: Option<BehaviorState>, // state the transition leaves (any state if unset)
+    pub to: BehaviorState,
+    #[serde(default)]
+    pub when: TransitionCondition,
+}
+
+// Per-boid behavioural state machine
+#[derive(Deserialize, Debug, Clone)]
+pub struct BehaviorConfig {
+    #[serde(default)]
+    pub initial: BehaviorState, // state of new boids
+    #[serde(default)]
+    pub flocking: StateWeights,
+    #[serde(default)]
+    pub fleeing: StateWeights,
+    #[serde(default)]
+    pub foraging: StateWeights,
+    #[serde(default)]
+    pub resting: StateWeights,
+    #[serde(default)]
+    pub transitions: Vec<TransitionConfig>, // checked in order, the first one matching fires
+}
+
+// The top-level config struct
+#[derive(Deserialize, Debug, Clone)]
 pub struct Config {
     pub resolution: Resolution,
     pub position: Position,
     pub boids: usize, // number of boids
     pub boids_config: BoidsConfig,
+    #[serde(default)]
+    pub food: Vec<FoodSourceConfig>, // food sources (life cycle, evolution or foraging)
+    #[serde(default)]
+    pub obstacles: Vec<ObstacleConfig>, // static or moving obstacles
+    #[serde(default)]
+    pub zones: Vec<ZoneConfig>, // zones overriding the boid parameters
+    pub map: Option<MapConfig>, // optional bitmap world map (replaces resolution)
+    pub morph: Option<MorphConfig>, // optional shape morphing timeline
+    #[serde(default)]
+    pub roosts: Vec<RoostConfig>, // roosts of the day cycle (roosting)
+    pub behavior: Option<BehaviorConfig>, // optional per-boid behavioural state machine
+    pub spawn: Option<SpawnConfig>, // optional initial placement and velocities of the boids
+    pub scenario: Option<ScenarioConfig>, // optional exact initial conditions (replaces spawn)
+    #[serde(default)]
+    pub emitters: Vec<EmitterConfig>, // emitters spawning boids during the run
+    #[serde(default)]
+    pub sinks: Vec<SinkConfig>, // sinks removing the boids entering them
+    #[serde(skip)]
+    pub schedules: Vec<(String, ScheduleConfig)>, // parameters given a schedule in boids_config
 }
 
 // --- loading function ---
@@ -57,7 +888,7 @@ pub struct Config {
 /// # Returns
 ///
 /// * Result<Config, Box<dyn std::error::Error>> - Returns the loaded Config struct
-/// or an error of file reading or parsing fails.
+///   or an error of file reading or parsing fails.
 pub fn load_config<P: AsRef<Path>>(path: P) -> Result<Config, Box<dyn std::error::Error>> {
     // open the file specified by the path
     let mut file = File::open(path)?;
@@ -65,8 +896,229 @@ pub fn load_config<P: AsRef<Path>>(path: P) -> Result<Config, Box<dyn std::error
     let mut contents = String::new();
     // Read the entire file into the buffer
     file.read_to_string(&mut contents)?;
-    // Parse the YAML string into the Config struct using serde_yaml
-    let config: Config = serde_yaml::from_str(&contents)?;
+    // Parse the configuration and return it
+    parse_config(&contents)
+}
+
+/// Parses the content of a config file.
+///
+/// # Arguments
+///
+/// * contents - the YAML text of the configuration.
+///
+/// # Returns
+///
+/// * Result<Config, Box<dyn std::error::Error>> - The configuration, with the parameter
+///   schedules taken out of boids_config and each scheduled parameter at its value at
+///   step 0, or an error if the YAML or a schedule is invalid.
+fn parse_config(contents: &str) -> Result<Config, Box<dyn std::error::Error>> {
+    // Parse the YAML string, take the parameter schedules out of boids_config,
+    // then build the Config struct using serde_yaml
+    let mut value: serde_yaml::Value = serde_yaml::from_str(contents)?;
+    let mut schedules = Vec::new();
+    if let Some(params) = value.get_mut("boids_config") {
+        take_schedules(params, "", &mut schedules)?;
+    }
+    let mut config: Config = serde_yaml::from_value(value)?;
+    // Check the scheduled parameters exist and can take every scheduled value, then
+    // set their start values
+    for (name, schedule) in &schedules {
+        let param = config
+            .boids_config
+            .param(name)
+            .map_err(|e| format!("boids_config.{}: {}", name, e))?;
+        let (min, max) = schedule.range();
+        param
+            .check_range(min, max)
+            .map_err(|e| format!("boids_config.{}: {}", name, e))?;
+        param.set(&mut config.boids_config, schedule.value_at(0));
+    }
+    config.schedules = schedules;
     // Return the successfully parsed configuration
     Ok(config)
 }
+
+/// Takes the schedules given in place of numeric boids_config parameters out of the
+/// YAML, searching the sections recursively. Each schedule is replaced by a 0, which
+/// fits any numeric field until the start value is set.
+///
+/// # Arguments
+///
+/// * value - the YAML mapping to search.
+/// * prefix - the dotted path of the mapping inside boids_config ("" at the top).
+/// * schedules - the schedules found so far, by dotted parameter path.
+///
+/// # Returns
+///
+/// * Result<(), Box<dyn std::error::Error>> - An error if a schedule is invalid.
+fn take_schedules(
+    value: &mut serde_yaml::Value,
+    prefix: &str,
+    schedules: &mut Vec<(String, ScheduleConfig)>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let Some(params) = value.as_mapping_mut() else {
+        return Ok(());
+    };
+    for (key, param) in params.iter_mut() {
+        let Some(key) = key.as_str() else {
+            continue;
+        };
+        let name = if prefix.is_empty() {
+            key.to_string()
+        } else {
+            format!("{}.{}", prefix, key)
+        };
+        let is_schedule = matches!(
+            param.get("type").and_then(|kind| kind.as_str()),
+            Some("keyframes" | "sine")
+        );
+        if !is_schedule {
+            take_schedules(param, &name, schedules)?;
+            continue;
+        }
+        let schedule: ScheduleConfig = serde_yaml::from_value(param.clone())
+            .map_err(|e| format!("boids_config.{}: {}", name, e))?;
+        let schedule = schedule
+            .validated()
+            .map_err(|e| format!("boids_config.{}: {}", name, e))?;
+        *param = serde_yaml::Value::from(0);
+        schedules.push((name, schedule));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HEADER: &str = "
+resolution: { x: 800.0, y: 600.0 }
+position: { x: 0, y: 0 }
+boids: 10
+boids_config:
+  protected_range: 8.0
+  visible_range: 40.0
+  avoidfactor: 0.05
+  centeringfactor: 0.0005
+  turnfactor: 0.2
+  margin: 100.0
+  maxspeed: 6.0
+  minspeed: 3.0
+  dt: 1.0
+  scale: false
+  trails_len: 0.1
+";
+
+    #[test]
+    fn keyframe_and_sine_schedules_are_parsed() {
+        let contents = format!(
+            "{}{}",
+            HEADER,
+            "  matchingfactor: { type: keyframes, keys: [[100, 0.05], [0, 0.01]] }
+  avoidance:
+    mode: orca
+    radius: 4.0
+    time_horizon: { type: sine, mean: 10.0, amplitude: 5.0, period: 40.0 }
+  startle:
+    probability: 0.5
+    delay: 2
+    duration: { type: keyframes, keys: [[0, 12.6], [10, 20.0]], repeat: true }
+    refractory: 50
+    turn_angle: 90.0
+    speed_boost: 1.5
+"
+        );
+        let config = parse_config(&contents).unwrap();
+        let names: Vec<&str> = config
+            .schedules
+            .iter()
+            .map(|(name, _)| name.as_str())
+            .collect();
+        assert_eq!(
+            names,
+            [
+                "matchingfactor",
+                "avoidance.time_horizon",
+                "startle.duration"
+            ]
+        );
+
+        // Each scheduled parameter starts at its value at step 0
+        let params = config.boids_config;
+        assert_eq!(params.matchingfactor, 0.01);
+        let AvoidanceConfig::Orca {
+            radius,
+            time_horizon,
+        } = params.avoidance
+        else {
+            panic!("expected orca avoidance, got {:?}", params.avoidance);
+        };
+        assert_eq!((radius, time_horizon), (4.0, 10.0));
+        assert_eq!(params.startle.unwrap().duration, 13); // Rounded for an integer field
+
+        // Keyframes are sorted and interpolated, the sine peaks a quarter period in
+        let value = |i: usize, step: u64| config.schedules[i].1.value_at(step);
+        assert!((value(0, 50) - 0.03).abs() < 1e-6);
+        assert_eq!(value(0, 500), 0.05);
+        assert!((value(1, 10) - 15.0).abs() < 1e-4);
+        assert!((value(2, 15) - value(2, 5)).abs() < 1e-6); // Repeats every 10 steps
+    }
+
+    #[test]
+    fn schedules_of_missing_parameters_are_rejected() {
+        let schedule = "{ type: sine, mean: 1.0, amplitude: 0.5, period: 10.0 }";
+        for (params, message) in [
+            (
+                format!("  matchingfactor: 0.05\n  cohesion: {}\n", schedule),
+                "unknown parameter",
+            ),
+            (
+                format!(
+                    "  matchingfactor: 0.05\n  avoidance: {{ mode: separation, radius: {} }}\n",
+                    schedule
+                ),
+                "not enabled",
+            ),
+        ] {
+            let error = parse_config(&format!("{}{}", HEADER, params)).unwrap_err();
+            assert!(error.to_string().contains(message), "{}", error);
+        }
+    }
+
+    #[test]
+    fn schedules_must_fit_the_parameter_type() {
+        let contents = format!(
+            "{}  matchingfactor: 0.05
+  startle:
+    probability: 0.5
+    delay: 2
+    duration: {{ type: sine, mean: 10.0, amplitude: 20.0, period: 100.0 }}
+    refractory: 50
+    turn_angle: 90.0
+    speed_boost: 1.5
+",
+            HEADER
+        );
+        let error = parse_config(&contents).unwrap_err();
+        assert!(
+            error.to_string().contains("boids_config.startle.duration"),
+            "{}",
+            error
+        );
+        assert!(error.to_string().contains("integer"), "{}", error);
+    }
+
+    #[test]
+    fn schedules_must_be_valid() {
+        let contents = format!(
+            "{}  matchingfactor: {{ type: sine, mean: 0.05, amplitude: 0.01, period: 0.0 }}\n",
+            HEADER
+        );
+        let error = parse_config(&contents).unwrap_err();
+        assert!(
+            error.to_string().contains("boids_config.matchingfactor"),
+            "{}",
+            error
+        );
+    }
+}
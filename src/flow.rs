@@ -0,0 +1,120 @@
+// src/flow.rs
+// Through-flow: emitters spawn boids at a steady rate with a heading distribution,
+// sinks remove the boids entering them (migration corridors, open boundaries).
+
+use ggez::glam::Vec2;
+use rand::Rng;
+
+use crate::config::{EmitterConfig, SinkConfig};
+
+// --- Emitter Struct Definition ---
+
+#[derive(Debug, Clone, Copy)]
+pub struct Emitter {
+    pub pos: Vec2,
+    pub radius: f32,       // boids spawn at a random point of this disk
+    rate: f32,             // boids spawned per step
+    heading: f32,          // mean initial heading, in radians
+    spread: f32,           // initial headings vary uniformly by up to this, in radians
+    speed: f32,            // initial speed
+    max_population: usize, // no boids are spawned while the population is at this size
+    pending: f32,          // fraction of a boid carried over to the next step
+}
+
+// --- Emitter Implementation ---
+
+impl Emitter {
+    /// Creates a new Emitter from its configuration.
+    ///
+    /// # Arguments
+    ///
+    /// * 'config' - The EmitterConfig read from boids.yaml.
+    ///
+    /// # Returns
+    ///
+    /// * 'Self' - A new Emitter instance.
+    pub fn new(config: &EmitterConfig) -> Self {
+        Emitter {
+            pos: Vec2::new(config.x, config.y),
+            radius: config.radius,
+            rate: config.rate.max(0.0),
+            heading: config.heading.to_radians(),
+            spread: config.spread.abs().to_radians(),
+            speed: config.speed,
+            max_population: config.max_population,
+            pending: 0.0,
+        }
+    }
+
+    /// Advances the emitter by one step and returns the number of boids to spawn,
+    /// accumulating fractional rates over the steps.
+    ///
+    /// # Arguments
+    ///
+    /// * 'population' - The current number of boids.
+    ///
+    /// # Returns
+    ///
+    /// * 'usize' - The number of boids to spawn this step.
+    pub fn emit(&mut self, population: usize) -> usize {
+        if population >= self.max_population {
+            self.pending = 0.0; // Do not release a burst once there is room again
+            return 0;
+        }
+        self.pending += self.rate;
+        let count = (self.pending.floor() as usize).min(self.max_population - population);
+        self.pending -= self.pending.floor();
+        count
+    }
+
+    /// Draws the initial position and velocity of a spawned boid.
+    ///
+    /// # Arguments
+    ///
+    /// * 'rng' - A mutable reference to a random number generator.
+    ///
+    /// # Returns
+    ///
+    /// * '(Vec2, Vec2)' - The position and velocity of the new boid.
+    pub fn spawn_state(&self, rng: &mut impl Rng) -> (Vec2, Vec2) {
+        // Uniform point of the disk
+        let angle = rng.random::<f32>() * 2.0 * std::f32::consts::PI;
+        let dist = rng.random::<f32>().sqrt() * self.radius;
+        let pos = self.pos + Vec2::from_angle(angle) * dist;
+        let heading = self.heading + (rng.random::<f32>() * 2.0 - 1.0) * self.spread;
+        (pos, Vec2::from_angle(heading) * self.speed)
+    }
+}
+
+// --- Sink Struct Definition ---
+
+#[derive(Debug, Clone, Copy)]
+pub struct Sink {
+    pub pos: Vec2,
+    pub radius: f32, // boids entering this disk are removed
+}
+
+// --- Sink Implementation ---
+
+impl Sink {
+    /// Creates a new Sink from its configuration.
+    ///
+    /// # Arguments
+    ///
+    /// * 'config' - The SinkConfig read from boids.yaml.
+    ///
+    /// # Returns
+    ///
+    /// * 'Self' - A new Sink instance.
+    pub fn new(config: &SinkConfig) -> Self {
+        Sink {
+            pos: Vec2::new(config.x, config.y),
+            radius: config.radius,
+        }
+    }
+
+    /// Returns true if a point is inside the sink.
+    pub fn contains(&self, p: Vec2) -> bool {
+        self.pos.distance_squared(p) <= self.radius * self.radius
+    }
+}
@@ -0,0 +1,306 @@
+// src/font.rs
+// A tiny built-in 5x7 bitmap font, used to render text into target shapes.
+
+pub const GLYPH_WIDTH: usize = 5;
+pub const GLYPH_HEIGHT: usize = 7;
+
+// Rows of every glyph, '#' for a filled pixel
+const GLYPHS: [(char, [&str; GLYPH_HEIGHT]); 44] = [
+    (
+        'A',
+        [
+            " ### ", "#   #", "#   #", "#####", "#   #", "#   #", "#   #",
+        ],
+    ),
+    (
+        'B',
+        [
+            "#### ", "#   #", "#   #", "#### ", "#   #", "#   #", "#### ",
+        ],
+    ),
+    (
+        'C',
+        [
+            " ### ", "#   #", "#    ", "#    ", "#    ", "#   #", " ### ",
+        ],
+    ),
+    (
+        'D',
+        [
+            "#### ", "#   #", "#   #", "#   #", "#   #", "#   #", "#### ",
+        ],
+    ),
+    (
+        'E',
+        [
+            "#####", "#    ", "#    ", "#### ", "#    ", "#    ", "#####",
+        ],
+    ),
+    (
+        'F',
+        [
+            "#####", "#    ", "#    ", "#### ", "#    ", "#    ", "#    ",
+        ],
+    ),
+    (
+        'G',
+        [
+            " ### ", "#   #", "#    ", "# ###", "#   #", "#   #", " ####",
+        ],
+    ),
+    (
+        'H',
+        [
+            "#   #", "#   #", "#   #", "#####", "#   #", "#   #", "#   #",
+        ],
+    ),
+    (
+        'I',
+        [
+            " ### ", "  #  ", "  #  ", "  #  ", "  #  ", "  #  ", " ### ",
+        ],
+    ),
+    (
+        'J',
+        [
+            "  ###", "   # ", "   # ", "   # ", "   # ", "#  # ", " ##  ",
+        ],
+    ),
+    (
+        'K',
+        [
+            "#   #", "#  # ", "# #  ", "##   ", "# #  ", "#  # ", "#   #",
+        ],
+    ),
+    (
+        'L',
+        [
+            "#    ", "#    ", "#    ", "#    ", "#    ", "#    ", "#####",
+        ],
+    ),
+    (
+        'M',
+        [
+            "#   #", "## ##", "# # #", "# # #", "#   #", "#   #", "#   #",
+        ],
+    ),
+    (
+        'N',
+        [
+            "#   #", "#   #", "##  #", "# # #", "#  ##", "#   #", "#   #",
+        ],
+    ),
+    (
+        'O',
+        [
+            " ### ", "#   #", "#   #", "#   #", "#   #", "#   #", " ### ",
+        ],
+    ),
+    (
+        'P',
+        [
+            "#### ", "#   #", "#   #", "#### ", "#    ", "#    ", "#    ",
+        ],
+    ),
+    (
+        'Q',
+        [
+            " ### ", "#   #", "#   #", "#   #", "# # #", "#  # ", " ## #",
+        ],
+    ),
+    (
+        'R',
+        [
+            "#### ", "#   #", "#   #", "#### ", "# #  ", "#  # ", "#   #",
+        ],
+    ),
+    (
+        'S',
+        [
+            " ####", "#    ", "#    ", " ### ", "    #", "    #", "#### ",
+        ],
+    ),
+    (
+        'T',
+        [
+            "#####", "  #  ", "  #  ", "  #  ", "  #  ", "  #  ", "  #  ",
+        ],
+    ),
+    (
+        'U',
+        [
+            "#   #", "#   #", "#   #", "#   #", "#   #", "#   #", " ### ",
+        ],
+    ),
+    (
+        'V',
+        [
+            "#   #", "#   #", "#   #", "#   #", "#   #", " # # ", "  #  ",
+        ],
+    ),
+    (
+        'W',
+        [
+            "#   #", "#   #", "#   #", "# # #", "# # #", "# # #", " # # ",
+        ],
+    ),
+    (
+        'X',
+        [
+            "#   #", "#   #", " # # ", "  #  ", " # # ", "#   #", "#   #",
+        ],
+    ),
+    (
+        'Y',
+        [
+            "#   #", "#   #", " # # ", "  #  ", "  #  ", "  #  ", "  #  ",
+        ],
+    ),
+    (
+        'Z',
+        [
+            "#####", "    #", "   # ", "  #  ", " #   ", "#    ", "#####",
+        ],
+    ),
+    (
+        '0',
+        [
+            " ### ", "#   #", "#  ##", "# # #", "##  #", "#   #", " ### ",
+        ],
+    ),
+    (
+        '1',
+        [
+            "  #  ", " ##  ", "  #  ", "  #  ", "  #  ", "  #  ", " ### ",
+        ],
+    ),
+    (
+        '2',
+        [
+            " ### ", "#   #", "    #", "   # ", "  #  ", " #   ", "#####",
+        ],
+    ),
+    (
+        '3',
+        [
+            "#####", "   # ", "  #  ", "   # ", "    #", "#   #", " ### ",
+        ],
+    ),
+    (
+        '4',
+        [
+            "   # ", "  ## ", " # # ", "#  # ", "#####", "   # ", "   # ",
+        ],
+    ),
+    (
+        '5',
+        [
+            "#####", "#    ", "#### ", "    #", "    #", "#   #", " ### ",
+        ],
+    ),
+    (
+        '6',
+        [
+            "  ## ", " #   ", "#    ", "#### ", "#   #", "#   #", " ### ",
+        ],
+    ),
+    (
+        '7',
+        [
+            "#####", "    #", "   # ", "  #  ", " #   ", " #   ", " #   ",
+        ],
+    ),
+    (
+        '8',
+        [
+            " ### ", "#   #", "#   #", " ### ", "#   #", "#   #", " ### ",
+        ],
+    ),
+    (
+        '9',
+        [
+            " ### ", "#   #", "#   #", " ####", "    #", "   # ", " ##  ",
+        ],
+    ),
+    (
+        ' ',
+        [
+            "     ", "     ", "     ", "     ", "     ", "     ", "     ",
+        ],
+    ),
+    (
+        '.',
+        [
+            "     ", "     ", "     ", "     ", "     ", " ##  ", " ##  ",
+        ],
+    ),
+    (
+        ',',
+        [
+            "     ", "     ", "     ", "     ", " ##  ", "  #  ", " #   ",
+        ],
+    ),
+    (
+        '!',
+        [
+            "  #  ", "  #  ", "  #  ", "  #  ", "  #  ", "     ", "  #  ",
+        ],
+    ),
+    (
+        '?',
+        [
+            " ### ", "#   #", "    #", "   # ", "  #  ", "     ", "  #  ",
+        ],
+    ),
+    (
+        '-',
+        [
+            "     ", "     ", "     ", "#####", "     ", "     ", "     ",
+        ],
+    ),
+    (
+        ':',
+        [
+            "     ", " ##  ", " ##  ", "     ", " ##  ", " ##  ", "     ",
+        ],
+    ),
+    (
+        '\'',
+        [
+            "  #  ", "  #  ", " #   ", "     ", "     ", "     ", "     ",
+        ],
+    ),
+];
+
+/// Renders a line of text into a pixel mask, one column of space between glyphs.
+/// Lowercase letters are drawn as uppercase and unknown characters as '?'.
+///
+/// # Arguments
+///
+/// * 'text' - The text to render.
+///
+/// # Returns
+///
+/// * '(Vec<bool>, usize, usize)' - The row-major mask (true for filled pixels),
+///   its width and its height.
+pub fn render_text(text: &str) -> (Vec<bool>, usize, usize) {
+    let chars: Vec<char> = text.chars().map(|c| c.to_ascii_uppercase()).collect();
+    let width = (chars.len() * (GLYPH_WIDTH + 1)).saturating_sub(1).max(1);
+    let mut mask = vec![false; width * GLYPH_HEIGHT];
+    for (i, c) in chars.iter().enumerate() {
+        let rows = GLYPHS
+            .iter()
+            .find(|(glyph, _)| glyph == c)
+            .or_else(|| GLYPHS.iter().find(|(glyph, _)| *glyph == '?'))
+            .map(|(_, rows)| rows)
+            .expect("the font has a '?' glyph");
+        for (y, row) in rows.iter().enumerate() {
+            for (x, pixel) in row.chars().enumerate() {
+                if pixel == '#' {
+                    mask[y * width + i * (GLYPH_WIDTH + 1) + x] = true;
+                }
+            }
+        }
+    }
+    (mask, width, GLYPH_HEIGHT)
+}
@@ -0,0 +1,192 @@
+// src/food.rs
+// Defines the food sources boids feed on to replenish their energy, and the
+// foraging steering that brings boids to them.
+
+use ggez::glam::Vec2;
+
+use crate::boids::Boid;
+use crate::config::{FoodSourceConfig, ForagingConfig};
+
+// --- FoodSource Struct Definition ---
+
+#[derive(Debug, Clone)]
+pub struct FoodSource {
+    pub pos: Vec2,
+    pub radius: f32,
+    pub energy_rate: f32,
+    pub amount: f32,      // food left (INFINITY for an unlimited source)
+    pub capacity: f32,    // maximum amount, reached again through regrowth
+    regrowth: f32,        // food added back per step
+    share: f32,           // fraction of energy_rate each feeder gets this step
+    pub consumed: f32,    // total food eaten since the start
+    pub empty_steps: u64, // number of steps the source spent depleted
+    pub feeders: usize,   // boids inside the source during the last step
+}
+
+// --- FoodSource Implementation ---
+
+impl FoodSource {
+    /// Creates a new FoodSource from its configuration.
+    ///
+    /// # Arguments
+    ///
+    /// * 'config' - The FoodSourceConfig read from boids.yaml.
+    ///
+    /// # Returns
+    ///
+    /// * 'Self' - A new FoodSource instance.
+    pub fn new(config: &FoodSourceConfig) -> Self {
+        let capacity = config.amount.unwrap_or(f32::INFINITY);
+        FoodSource {
+            pos: Vec2::new(config.x, config.y),
+            radius: config.radius,
+            energy_rate: config.energy_rate,
+            amount: capacity,
+            capacity,
+            regrowth: config.regrowth,
+            share: 1.0,
+            consumed: 0.0,
+            empty_steps: 0,
+            feeders: 0,
+        }
+    }
+
+    /// Returns true if the position is inside the source.
+    pub fn contains(&self, pos: Vec2) -> bool {
+        pos.distance_squared(self.pos) <= self.radius * self.radius
+    }
+
+    /// Returns true if the source has no food left.
+    pub fn is_depleted(&self) -> bool {
+        self.amount <= 0.0
+    }
+
+    /// Returns the energy gained during one step by a boid at the given position.
+    ///
+    /// # Arguments
+    ///
+    /// * 'pos' - The position of the feeding boid.
+    ///
+    /// # Returns
+    ///
+    /// * 'f32' - The share of energy_rate the boid gets if the position is inside the source,
+    ///   0.0 otherwise.
+    pub fn energy_at(&self, pos: Vec2) -> f32 {
+        if self.contains(pos) {
+            self.energy_rate * self.share
+        } else {
+            0.0
+        }
+    }
+
+    /// Regrows the source, then takes the food eaten during one step.
+    /// When the feeders ask for more than is left, the remaining food is split
+    /// equally between them.
+    ///
+    /// # Arguments
+    ///
+    /// * 'feeders' - The number of boids inside the source.
+    pub fn serve(&mut self, feeders: usize) {
+        self.amount = (self.amount + self.regrowth).min(self.capacity);
+        let demand = feeders as f32 * self.energy_rate;
+        let eaten = demand.min(self.amount);
+        self.feeders = feeders;
+        self.share = if demand > 0.0 { eaten / demand } else { 1.0 };
+        self.consumed += eaten;
+        self.amount -= eaten;
+        if self.is_depleted() {
+            self.empty_steps += 1;
+        }
+    }
+}
+
+/// Calculates the velocity change steering a boid towards the closest food source
+/// it can sense, i.e. whose edge is within the sensing range. Depleted sources are
+/// ignored, and boids already inside a source are not steered.
+///
+/// # Arguments
+///
+/// * 'boid' - The foraging boid.
+/// * 'food' - A slice of all the food sources of the simulation.
+/// * 'foraging' - The ForagingConfig parameters.
+///
+/// # Returns
+///
+/// * 'Vec2' - The foraging velocity change.
+pub fn foraging_velocity_change(
+    boid: &Boid,
+    food: &[FoodSource],
+    foraging: &ForagingConfig,
+) -> Vec2 {
+    let mut closest: Option<(f32, Vec2)> = None;
+    for source in food.iter().filter(|source| !source.is_depleted()) {
+        let to_source = source.pos - boid.pos;
+        let edge_dist = to_source.length() - source.radius;
+        if edge_dist <= 0.0 {
+            return Vec2::ZERO; // Already feeding
+        }
+        if edge_dist <= foraging.sensing_range && closest.is_none_or(|(d, _)| edge_dist < d) {
+            closest = Some((edge_dist, to_source));
+        }
+    }
+    match closest {
+        Some((_, to_source)) => to_source.normalize_or_zero() * foraging.factor,
+        None => Vec2::ZERO,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn source(amount: Option<f32>, regrowth: f32) -> FoodSource {
+        FoodSource::new(&FoodSourceConfig {
+            x: 0.0,
+            y: 0.0,
+            radius: 10.0,
+            energy_rate: 2.0,
+            amount,
+            regrowth,
+        })
+    }
+
+    #[test]
+    fn depleted_source_shares_what_is_left() {
+        let mut source = source(Some(5.0), 0.0);
+        source.serve(2); // Asks for 4 of 5
+        assert_eq!(source.energy_at(Vec2::ZERO), 2.0);
+        assert_eq!(source.amount, 1.0);
+
+        source.serve(4); // Asks for 8, 1 left: an eighth each
+        assert_eq!(source.energy_at(Vec2::ZERO), 0.25);
+        assert!(source.is_depleted());
+        assert_eq!((source.consumed, source.empty_steps), (5.0, 1));
+
+        source.serve(1);
+        assert_eq!(source.energy_at(Vec2::ZERO), 0.0);
+        assert_eq!(source.energy_at(Vec2::new(20.0, 0.0)), 0.0);
+        assert_eq!(source.empty_steps, 2);
+    }
+
+    #[test]
+    fn regrowth_refills_up_to_the_capacity() {
+        let mut source = source(Some(3.0), 1.0);
+        source.serve(3); // Regrows to the capacity first, then 6 asked for 3
+        assert_eq!(source.energy_at(Vec2::ZERO), 1.0);
+        source.serve(0);
+        source.serve(0);
+        assert_eq!(source.amount, 2.0);
+        for _ in 0..5 {
+            source.serve(0);
+        }
+        assert_eq!(source.amount, 3.0);
+    }
+
+    #[test]
+    fn unlimited_source_serves_everyone_fully() {
+        let mut source = source(None, 0.0);
+        source.serve(1000);
+        assert_eq!(source.energy_at(Vec2::ZERO), 2.0);
+        assert!(!source.is_depleted());
+    }
+}
@@ -0,0 +1,133 @@
+// src/formation.rs
+// Formation flight: boids take slots relative to a leader (V, echelon, line
+// or grid) and hold them with spring-like steering.
+
+use std::collections::HashMap;
+
+use ggez::glam::Vec2;
+use uuid::Uuid;
+
+use crate::boids::Boid;
+use crate::config::{FormationConfig, FormationShape};
+
+// --- Formation Struct Definition ---
+
+#[derive(Debug, Clone)]
+pub struct Formation {
+    pub leader: Uuid,
+    pub members: Vec<Uuid>, // member of each slot behind the leader, in slot order
+}
+
+// --- Formation Implementation ---
+
+impl Formation {
+    /// Returns the world position of every member slot, for a leader at the given
+    /// position and velocity.
+    ///
+    /// # Arguments
+    ///
+    /// * 'leader' - The leader of the formation.
+    /// * 'config' - The FormationConfig parameters.
+    /// * 'count' - The number of slots.
+    pub fn slot_targets(leader: &Boid, config: &FormationConfig, count: usize) -> Vec<Vec2> {
+        let heading = leader.vel.try_normalize().unwrap_or(Vec2::X);
+        slot_offsets(&config.shape, config.spacing, count)
+            .into_iter()
+            .map(|offset| leader.pos + heading.rotate(offset))
+            .collect()
+    }
+
+    /// Reassigns the slots to the members, greedily matching the closest
+    /// member-slot pairs first.
+    ///
+    /// # Arguments
+    ///
+    /// * 'boids' - A slice of all the boids of the simulation.
+    /// * 'index_of' - The index of each boid in the slice, by id.
+    /// * 'targets' - The world position of every slot.
+    pub fn assign_slots(
+        &mut self,
+        boids: &[Boid],
+        index_of: &HashMap<Uuid, usize>,
+        targets: &[Vec2],
+    ) {
+        let mut pairs: Vec<(f32, usize, usize)> = Vec::new(); // (distance², member, slot)
+        for (m, id) in self.members.iter().enumerate() {
+            let pos = boids[index_of[id]].pos;
+            for (s, target) in targets.iter().enumerate() {
+                pairs.push((pos.distance_squared(*target), m, s));
+            }
+        }
+        pairs.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+        let mut slots: Vec<Option<Uuid>> = vec![None; targets.len()];
+        let mut placed = vec![false; self.members.len()];
+        for (_, m, s) in pairs {
+            if !placed[m] && slots[s].is_none() {
+                slots[s] = Some(self.members[m]);
+                placed[m] = true;
+            }
+        }
+        self.members = slots.into_iter().flatten().collect();
+    }
+}
+
+/// Returns the offsets of the member slots in the leader's frame (x forward, y to the
+/// right), the leader being at the origin.
+///
+/// # Arguments
+///
+/// * 'shape' - The shape of the formation.
+/// * 'spacing' - The distance between neighboring slots.
+/// * 'count' - The number of member slots.
+pub fn slot_offsets(shape: &FormationShape, spacing: f32, count: usize) -> Vec<Vec2> {
+    (1..=count)
+        .map(|i| match *shape {
+            FormationShape::V { angle } => {
+                // Alternate sides, one rank further back every two slots
+                let rank = i.div_ceil(2) as f32;
+                let side = if i % 2 == 1 { 1.0 } else { -1.0 };
+                let arm = Vec2::from_angle(angle.to_radians());
+                Vec2::new(-arm.x, side * arm.y) * rank * spacing
+            }
+            FormationShape::Echelon { angle } => {
+                let arm = Vec2::from_angle(angle.to_radians());
+                Vec2::new(-arm.x, arm.y) * i as f32 * spacing
+            }
+            FormationShape::Line => {
+                // Abreast, alternating sides
+                let rank = i.div_ceil(2) as f32;
+                let side = if i % 2 == 1 { 1.0 } else { -1.0 };
+                Vec2::new(0.0, side * rank * spacing)
+            }
+            FormationShape::Grid { columns } => {
+                // Rows behind the leader, who takes the first cell of the first row
+                let columns = columns.max(1) as usize;
+                let (row, col) = (i / columns, i % columns);
+                Vec2::new(-(row as f32), col as f32) * spacing
+            }
+        })
+        .collect()
+}
+
+/// Calculates the spring-like velocity change holding a member on its slot:
+/// pulled towards the slot and damped towards the leader's velocity.
+///
+/// # Arguments
+///
+/// * 'boid' - The member.
+/// * 'target' - The world position of its slot.
+/// * 'leader_vel' - The velocity of the leader.
+/// * 'config' - The FormationConfig parameters.
+///
+/// # Returns
+///
+/// * 'Vec2' - The formation keeping velocity change.
+pub fn slot_velocity_change(
+    boid: &Boid,
+    target: Vec2,
+    leader_vel: Vec2,
+    config: &FormationConfig,
+) -> Vec2 {
+    (target - boid.pos) * config.spring + (leader_vel - boid.vel) * config.damping
+}
@@ -0,0 +1,204 @@
+// src/genetics.rs
+// Heritable per-boid flocking parameters, their mutation, and the per-generation
+// statistics logged by the evolution mode.
+
+use rand::Rng;
+use rand_distr::{Distribution, Normal};
+
+use crate::config::BoidsConfig;
+
+// --- Genes Struct Definition ---
+
+// The flocking parameters each boid carries and passes on to its offspring
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Genes {
+    pub avoidfactor: f32,
+    pub matchingfactor: f32,
+    pub centeringfactor: f32,
+    pub protected_range: f32,
+    pub visible_range: f32,
+}
+
+// --- Genes Implementation ---
+
+impl Genes {
+    /// Number of genes, used when iterating over them as an array.
+    pub const COUNT: usize = 5;
+    /// Gene names, in the same order as to_array.
+    pub const NAMES: [&'static str; Genes::COUNT] = [
+        "avoidfactor",
+        "matchingfactor",
+        "centeringfactor",
+        "protected_range",
+        "visible_range",
+    ];
+
+    /// Creates the genes every boid starts with, taken from the global config.
+    ///
+    /// # Arguments
+    ///
+    /// * 'config' - A reference to the BoidsConfig parameters.
+    ///
+    /// # Returns
+    ///
+    /// * 'Self' - The genes matching the configured flocking parameters.
+    pub fn from_config(config: &BoidsConfig) -> Self {
+        Genes {
+            avoidfactor: config.avoidfactor,
+            matchingfactor: config.matchingfactor,
+            centeringfactor: config.centeringfactor,
+            protected_range: config.protected_range,
+            visible_range: config.visible_range,
+        }
+    }
+
+    /// Returns the genes as an array, in the order of Genes::NAMES.
+    pub fn to_array(self) -> [f32; Genes::COUNT] {
+        [
+            self.avoidfactor,
+            self.matchingfactor,
+            self.centeringfactor,
+            self.protected_range,
+            self.visible_range,
+        ]
+    }
+
+    /// Builds genes from an array, in the order of Genes::NAMES.
+    pub fn from_array(values: [f32; Genes::COUNT]) -> Self {
+        Genes {
+            avoidfactor: values[0],
+            matchingfactor: values[1],
+            centeringfactor: values[2],
+            protected_range: values[3],
+            visible_range: values[4],
+        }
+    }
+
+    /// Returns a mutated copy of the genes.
+    /// Each gene is multiplied by (1 + N(0, sigma)) and kept non-negative.
+    ///
+    /// # Arguments
+    ///
+    /// * 'sigma' - Relative standard deviation of the mutation.
+    /// * 'rng' - A mutable reference to a random number generator.
+    ///
+    /// # Returns
+    ///
+    /// * 'Genes' - The mutated genes.
+    pub fn mutate(&self, sigma: f32, rng: &mut impl Rng) -> Genes {
+        let Ok(normal) = Normal::new(0.0, sigma.max(0.0)) else {
+            return *self; // invalid sigma, no mutation
+        };
+        Genes::from_array(
+            self.to_array()
+                .map(|value| (value * (1.0 + normal.sample(rng))).max(0.0)),
+        )
+    }
+}
+
+// --- Selection ---
+
+/// Selects an individual by tournament: draws `size` random candidates and keeps the fittest.
+///
+/// # Arguments
+///
+/// * 'fitness' - The fitness of every individual (must not be empty).
+/// * 'size' - Number of candidates per tournament (at least 1).
+/// * 'rng' - A mutable reference to a random number generator.
+///
+/// # Returns
+///
+/// * 'usize' - The index of the selected individual.
+pub fn tournament_select(fitness: &[f32], size: usize, rng: &mut impl Rng) -> usize {
+    let mut best = rng.random_range(0..fitness.len());
+    for _ in 1..size.max(1) {
+        let candidate = rng.random_range(0..fitness.len());
+        if fitness[candidate] > fitness[best] {
+            best = candidate;
+        }
+    }
+    best
+}
+
+// --- GenerationStats Struct Definition ---
+
+// Distribution of the genes over the population at the end of a generation
+#[derive(Debug, Clone, Copy)]
+pub struct GenerationStats {
+    pub generation: u64,
+    pub step: u64,
+    pub population: usize,
+    pub mean_fitness: f32,
+    pub mean: Genes,
+    pub std_dev: Genes,
+}
+
+// --- GenerationStats Implementation ---
+
+impl GenerationStats {
+    /// Computes the mean and standard deviation of every gene over a population.
+    ///
+    /// # Arguments
+    ///
+    /// * 'generation' - Index of the generation.
+    /// * 'step' - Simulation step the generation ended at.
+    /// * 'genes' - The genes of every boid alive.
+    /// * 'fitness' - The fitness of every boid alive (same order as genes).
+    ///
+    /// # Returns
+    ///
+    /// * 'Self' - The statistics of the generation.
+    pub fn new(generation: u64, step: u64, genes: &[Genes], fitness: &[f32]) -> Self {
+        let n = genes.len().max(1) as f32;
+        let mut mean = [0.0; Genes::COUNT];
+        for g in genes {
+            for (m, v) in mean.iter_mut().zip(g.to_array()) {
+                *m += v / n;
+            }
+        }
+        let mut var = [0.0; Genes::COUNT];
+        for g in genes {
+            for ((s, m), v) in var.iter_mut().zip(mean).zip(g.to_array()) {
+                *s += (v - m) * (v - m) / n;
+            }
+        }
+        GenerationStats {
+            generation,
+            step,
+            population: genes.len(),
+            mean_fitness: fitness.iter().sum::<f32>() / n,
+            mean: Genes::from_array(mean),
+            std_dev: Genes::from_array(var.map(f32::sqrt)),
+        }
+    }
+
+    /// Returns the CSV header matching csv_row.
+    pub fn csv_header() -> String {
+        let mut columns = vec![
+            "generation".to_string(),
+            "step".to_string(),
+            "population".to_string(),
+            "mean_fitness".to_string(),
+        ];
+        for name in Genes::NAMES {
+            columns.push(format!("{}_mean", name));
+            columns.push(format!("{}_std", name));
+        }
+        columns.join(",")
+    }
+
+    /// Formats the statistics as a CSV row.
+    pub fn csv_row(&self) -> String {
+        let mut columns = vec![
+            self.generation.to_string(),
+            self.step.to_string(),
+            self.population.to_string(),
+            self.mean_fitness.to_string(),
+        ];
+        for (mean, std_dev) in self.mean.to_array().iter().zip(self.std_dev.to_array()) {
+            columns.push(mean.to_string());
+            columns.push(std_dev.to_string());
+        }
+        columns.join(",")
+    }
+}
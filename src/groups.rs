@@ -0,0 +1,80 @@
+// src/groups.rs
+// Hierarchical groups: boids belong to family units inside the flocks, weighting
+// their group mates more than the others for cohesion and alignment. Groups are
+// formed at startup or on the fly, and measured by their spread and separation.
+
+use std::collections::HashMap;
+
+use ggez::glam::Vec2;
+
+use crate::boids::Boid;
+
+/// Returns the center and size of every group, by group id.
+///
+/// # Arguments
+///
+/// * 'boids' - A slice of all the boids of the simulation.
+///
+/// # Returns
+///
+/// * 'HashMap<usize, (Vec2, usize)>' - The center and number of members of every group.
+pub fn group_centers(boids: &[Boid]) -> HashMap<usize, (Vec2, usize)> {
+    let mut sums: HashMap<usize, (Vec2, usize)> = HashMap::new();
+    for boid in boids {
+        if let Some(group) = boid.group {
+            let sum = sums.entry(group).or_insert((Vec2::ZERO, 0));
+            sum.0 += boid.pos;
+            sum.1 += 1;
+        }
+    }
+    for (center, count) in sums.values_mut() {
+        *center /= *count as f32;
+    }
+    sums
+}
+
+/// Measures the groups: how spread out each group is, and how far apart the
+/// groups are.
+///
+/// # Arguments
+///
+/// * 'boids' - A slice of all the boids of the simulation.
+///
+/// # Returns
+///
+/// * '(usize, f32, f32)' - The number of groups, the mean distance of the members
+///   from their group center, and the mean distance from each group center to the
+///   closest other one (0 with fewer than two groups).
+pub fn group_stats(boids: &[Boid]) -> (usize, f32, f32) {
+    let centers = group_centers(boids);
+    if centers.is_empty() {
+        return (0, 0.0, 0.0);
+    }
+
+    let (spread_sum, members) = boids
+        .iter()
+        .filter_map(|boid| Some(boid.pos.distance(centers.get(&boid.group?)?.0)))
+        .fold((0.0, 0), |(sum, count), d| (sum + d, count + 1));
+    let spread = spread_sum / members.max(1) as f32;
+
+    let points: Vec<Vec2> = centers.values().map(|(center, _)| *center).collect();
+    let distance = if points.len() < 2 {
+        0.0
+    } else {
+        let total: f32 = points
+            .iter()
+            .enumerate()
+            .map(|(i, a)| {
+                points
+                    .iter()
+                    .enumerate()
+                    .filter(|&(j, _)| j != i)
+                    .map(|(_, b)| a.distance(*b))
+                    .fold(f32::INFINITY, f32::min)
+            })
+            .sum();
+        total / points.len() as f32
+    };
+
+    (centers.len(), spread, distance)
+}
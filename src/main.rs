@@ -16,16 +16,61 @@ use ggez::{Context, ContextBuilder, GameResult}; // ggez core types
 use rand::Rng; // Import the Rng trait
 
 // --- Import local modules ---
+mod behavior;
 mod boids;
 mod color_utils;
 mod config;
+mod flow;
+mod font;
+mod food;
+mod formation;
+mod genetics;
+mod groups;
+mod morph;
+mod obstacles;
+mod orca;
+mod params;
+mod perception;
+mod pheromone;
+mod projection;
+mod roost;
+mod scenario;
+mod schedule;
+mod shapes;
 mod simulator;
-
-use crate::config::{load_config, Config}; // Import config loading function and struct
+mod spawn;
+mod startle;
+mod stats;
+mod timeline;
+mod ttc;
+mod world_map;
+mod zones;
+
+use crate::behavior::StateMachine; // Behavioural state machine
+use crate::config::{BehaviorState, Config, InteractionConfig, load_config}; // Import config loading function and struct
+use crate::flow::{Emitter, Sink}; // Through-flow emitters and sinks
+use crate::food::FoodSource; // Food sources for the life cycle
+use crate::genetics::GenerationStats; // Per-generation gene distribution
+use crate::morph::Morph; // Shape morphing
+use crate::obstacles::Obstacle; // Static and moving obstacles
+use crate::roost::Roost; // Roosts of the day cycle
+use crate::scenario::Scenario; // Exact initial conditions
+use crate::shapes::Shape; // Obstacle shapes, for drawing
 use crate::simulator::BoidSimulator; // Import the BoidSimulator
+use crate::stats::{CascadeStats, FoodPatchStats, IntakeStats}; // Foraging and cascade statistics
+use crate::timeline::Timeline; // Scripted events of a scenario
+use crate::world_map::WorldMap; // Bitmap world map
+use crate::zones::Zone; // Parameter override zones
 
 // --- Constants ---
 const CONFIG_PATH: &str = "boids.yaml"; // Path to the configuration file
+const READOUT_INTERVAL: usize = 100; // Ticks between two console readouts
+const EVOLUTION_LOG_PATH: &str = "evolution_log.csv"; // Per-generation gene distribution log
+const FOOD_LOG_PATH: &str = "food_log.csv"; // Food source depletion log (foraging only)
+const INTAKE_LOG_PATH: &str = "intake_log.csv"; // Per-boid intake export (foraging only)
+const PHEROMONE_IMAGE_PATH: &str = "pheromone.png"; // Pheromone field export
+const CASCADE_LOG_PATH: &str = "cascade_log.csv"; // Startle cascade propagation log
+const SCHEDULE_LOG_PATH: &str = "schedule_log.csv"; // Scheduled parameters and polarization log
 
 // --- Main Game State Struct ---
 
@@ -35,53 +80,260 @@ struct MainState {
     // rng: ThreadRng,           // Random number generator
     boid_mesh: Option<Mesh>, // Pre-built mesh for drawing boids efficiently
     show_trails: bool,       // Flag to control background clearing (trails effect)
+    color_by_state: bool,    // Color boids by behavioural state instead of heading
+    paused: bool,            // Simulation paused (by a timeline event or the Space key)
+    logged_generations: usize, // Number of generations already written to the evolution log
+    world_dims: (f32, f32),    // Size of the simulation area (the map's, or the resolution)
+    map_image: Option<graphics::Image>, // Overlay of the world map, one pixel per cell
+    map_cell_size: f32,        // Scale of the overlay
+    zone_mesh: Option<Mesh>,   // Faint overlay of the parameter override zones
+    roost_mesh: Option<Mesh>,  // Faint overlay of the roosts
+    emitter_mesh: Option<Mesh>, // Faint overlay of the emitters
+    sink_mesh: Option<Mesh>,   // Faint overlay of the sinks
 }
 
 impl MainState {
-    /// Creates a new MainState instance, initializing the simulation.
-    fn new(ctx: &mut Context, config: Config) -> GameResult<MainState> {
+    /// Creates a new MainState instance with its window resources (map overlay and
+    /// meshes), initializing the simulation.
+    fn new(
+        ctx: &mut Context,
+        config: Config,
+        world_map: Option<WorldMap>,
+        scenario: Option<Scenario>,
+    ) -> GameResult<MainState> {
+        // Keep an overlay of the map for drawing, the map itself goes to the simulator
+        let overlay = world_map
+            .as_ref()
+            .map(|map| (map.overlay_rgba(), map.cell_size()));
+        let mut state = Self::new_headless(config, world_map, scenario)?;
+        if let Some(((pixels, width, height), cell_size)) = overlay {
+            state.map_image = Some(graphics::Image::from_pixels(
+                ctx,
+                &pixels,
+                graphics::ImageFormat::Rgba8UnormSrgb,
+                width,
+                height,
+            ));
+            state.map_cell_size = cell_size;
+        }
+
+        // Build the initial meshes for drawing (the zones, emitters and sinks do not move)
+        let zone_shapes = state.simulator.zones().iter().map(|z| z.shape);
+        state.zone_mesh =
+            Self::build_shape_mesh(ctx, zone_shapes, Color::new(0.3, 0.5, 1.0, 0.08))?;
+        state.rebuild_roost_mesh(ctx)?;
+        let emitter_shapes = state.simulator.emitters().iter().map(|e| Shape::Circle {
+            center: e.pos,
+            radius: e.radius,
+        });
+        state.emitter_mesh =
+            Self::build_shape_mesh(ctx, emitter_shapes, Color::new(0.2, 0.8, 0.3, 0.15))?;
+        let sink_shapes = state.simulator.sinks().iter().map(|s| Shape::Circle {
+            center: s.pos,
+            radius: s.radius,
+        });
+        state.sink_mesh =
+            Self::build_shape_mesh(ctx, sink_shapes, Color::new(0.9, 0.2, 0.2, 0.15))?;
+        state.rebuild_boid_mesh(ctx)?;
+
+        Ok(state)
+    }
+
+    /// Creates a new MainState instance without window resources, initializing the
+    /// simulation (used as is by headless runs).
+    fn new_headless(
+        config: Config,
+        world_map: Option<WorldMap>,
+        scenario: Option<Scenario>,
+    ) -> GameResult<MainState> {
         let mut rng = rand::rng(); // Initialize the random number generator
 
+        // The world map, if any, sets the size of the simulation area
+        let world_dims = match &world_map {
+            Some(map) => map.world_dims(),
+            None => (config.resolution.x, config.resolution.y),
+        };
+
         // Create the BoidSimulator instance
         let mut simulator = BoidSimulator::new(
-            config.boids_config,                        // Pass boid-specific config
-            (config.resolution.x, config.resolution.y), // Pass screen dimensions
+            config.boids_config, // Pass boid-specific config
+            world_dims,          // Pass screen dimensions
         );
 
+        // Give the boids their behavioural state machine (before spawning, so that
+        // the states of a scenario are kept)
+        if let Some(behavior) = &config.behavior {
+            simulator.set_behavior(StateMachine::new(behavior));
+        }
+
         // --- Initialize Boids ---
-        // Calculate spawn area boundaries based on margins from config
-        let margin_x = config.resolution.x / 8.0; // Similar to Python script's border_distance
-        let margin_y = config.resolution.y / 8.0;
-        let x_min = margin_x;
-        let x_max = config.resolution.x - margin_x;
-        let y_min = margin_y;
-        let y_max = config.resolution.y - margin_y;
-
-        // Add the configured number of boids within the spawn area
-        for _ in 0..config.boids {
-            let x = rng.random_range(x_min..x_max);
-            let y = rng.random_range(y_min..y_max);
-            // Use ggez::glam::Vec2 here
-            simulator.add_boid(Vec2::new(x, y), &mut rng);
+        if let Some(scenario) = &scenario {
+            // Add the boids of the scenario with their exact initial state
+            let seed = config
+                .scenario
+                .as_ref()
+                .and_then(|s| s.seed)
+                .or(scenario.seed);
+            if let Some(seed) = seed {
+                simulator.set_seed(seed);
+            }
+            simulator.add_scenario(scenario, &mut rng);
+            simulator.set_timeline(Timeline::new(&scenario.timeline));
+        } else if let Some(spawn_config) = &config.spawn {
+            // Add the configured number of boids following the spawn section
+            let states = spawn::spawn_states(spawn_config, config.boids, world_dims, &mut rng)
+                .map_err(|e| {
+                    eprintln!("Error spawning boids: {}", e);
+                    ggez::GameError::ResourceLoadError(format!("Failed to spawn boids: {}", e))
+                })?;
+            for (pos, vel) in states {
+                simulator.add_boid_with_velocity(pos, vel, &mut rng);
+            }
+        } else {
+            // Calculate spawn area boundaries based on margins from config
+            let margin_x = world_dims.0 / 8.0; // Similar to Python script's border_distance
+            let margin_y = world_dims.1 / 8.0;
+            let x_min = margin_x;
+            let x_max = world_dims.0 - margin_x;
+            let y_min = margin_y;
+            let y_max = world_dims.1 - margin_y;
+
+            // Add the configured number of boids within the spawn area (the map's spawn zones if any)
+            for _ in 0..config.boids {
+                let pos = match world_map
+                    .as_ref()
+                    .and_then(|map| map.random_spawn_point(&mut rng))
+                {
+                    Some(pos) => pos,
+                    None => Vec2::new(
+                        rng.random_range(x_min..x_max),
+                        rng.random_range(y_min..y_max),
+                    ),
+                };
+                // Use ggez::glam::Vec2 here
+                simulator.add_boid(pos, &mut rng);
+            }
+        }
+
+        // Hand the map to the simulator
+        if let Some(map) = world_map {
+            simulator.set_world_map(map);
+        }
+
+        // Load the target shapes of the morph timeline
+        if let Some(morph_config) = &config.morph {
+            match Morph::new(morph_config) {
+                Ok(morph) => simulator.set_morph(morph),
+                Err(e) => {
+                    eprintln!("Error loading morph targets: {}", e);
+                    return Err(ggez::GameError::ResourceLoadError(format!(
+                        "Failed to load morph targets: {}",
+                        e
+                    )));
+                }
+            }
+        }
+
+        // Add the food sources replenishing the boids' energy
+        for source in &config.food {
+            simulator.add_food_source(FoodSource::new(source));
+        }
+
+        // Add the obstacles
+        for obstacle in &config.obstacles {
+            simulator.add_obstacle(Obstacle::new(obstacle));
+        }
+
+        // Add the zones overriding the boid parameters
+        for zone in &config.zones {
+            simulator.add_zone(Zone::new(zone));
+        }
+
+        // Add the roosts of the day cycle
+        for roost in &config.roosts {
+            simulator.add_roost(Roost::new(roost));
+        }
+
+        // Add the emitters and sinks of the through-flow
+        for emitter in &config.emitters {
+            simulator.add_emitter(Emitter::new(emitter));
+        }
+        for sink in &config.sinks {
+            simulator.add_sink(Sink::new(sink));
+        }
+
+        // Let the scheduled parameters follow their schedules
+        if let Err(e) = simulator.set_schedules(config.schedules.clone()) {
+            eprintln!("Error setting parameter schedules: {}", e);
+            return Err(ggez::GameError::ResourceLoadError(format!(
+                "Failed to set parameter schedules: {}",
+                e
+            )));
         }
 
+        // Color by behavioural state when the state machine is enabled
+        let color_by_state = config.behavior.is_some();
+
         // Initialize the main state
-        let mut state = MainState {
+        let state = MainState {
             simulator,
             config,
             // rng,
             boid_mesh: None,   // Mesh will be built in the first update/draw
             show_trails: true, // Start with trails enabled
+            color_by_state,
+            paused: false,
+            logged_generations: 0,
+            world_dims,
+            map_image: None,
+            map_cell_size: 1.0,
+            zone_mesh: None,
+            roost_mesh: None,
+            emitter_mesh: None,
+            sink_mesh: None,
         };
 
-        // Build the initial mesh for drawing
-        state.rebuild_boid_mesh(ctx)?;
+        // Start a fresh evolution log
+        if state.config.boids_config.evolution.is_some()
+            && let Err(e) = std::fs::write(EVOLUTION_LOG_PATH, GenerationStats::csv_header() + "\n")
+        {
+            eprintln!("Error creating '{}': {}", EVOLUTION_LOG_PATH, e);
+        }
+
+        // Start a fresh cascade log
+        if state.config.boids_config.startle.is_some()
+            && let Err(e) = std::fs::write(CASCADE_LOG_PATH, CascadeStats::csv_header() + "\n")
+        {
+            eprintln!("Error creating '{}': {}", CASCADE_LOG_PATH, e);
+        }
+
+        // Start a fresh food log
+        if state.config.boids_config.foraging.is_some()
+            && let Err(e) = std::fs::write(FOOD_LOG_PATH, FoodPatchStats::csv_header() + "\n")
+        {
+            eprintln!("Error creating '{}': {}", FOOD_LOG_PATH, e);
+        }
+
+        // Start a fresh schedule log
+        if !state.config.schedules.is_empty() {
+            let names: Vec<&str> = state
+                .config
+                .schedules
+                .iter()
+                .map(|(n, _)| n.as_str())
+                .collect();
+            let header = format!("step,polarization,{}\n", names.join(","));
+            if let Err(e) = std::fs::write(SCHEDULE_LOG_PATH, header) {
+                eprintln!("Error creating '{}': {}", SCHEDULE_LOG_PATH, e);
+            }
+        }
 
         Ok(state)
     }
 
     /// Rebuilds the mesh used to draw all boids.
     /// This is more efficient than drawing each boid individually every frame.
+    #[allow(clippy::needless_late_init)]
     fn rebuild_boid_mesh(&mut self, ctx: &mut Context) -> GameResult<()> {
         if self.simulator.boids.is_empty() {
             self.boid_mesh = None; // No mesh if no boids
@@ -95,14 +347,20 @@ impl MainState {
             .simulator
             .boids
             .iter()
-            .map(|b| b.get_color(&self.config.boids_config))
+            .map(|b| match b.alarm.escape() {
+                Some(_) => Color::WHITE, // Alarmed boids flash white
+                None if self.color_by_state => b.behavior.color(),
+                None => b.get_color(self.simulator.config()),
+            })
             .collect();
 
         // Create a new mesh builder for points
         let mut mesh_builder = graphics::MeshBuilder::new();
+        // Parameters in effect (the timeline may change them)
+        let boids_config = self.simulator.config();
         let size: f32;
-        if self.config.boids_config.scale {
-            size = self.config.boids_config.protected_range / 2.0;
+        if boids_config.scale {
+            size = boids_config.protected_range / 2.0;
         } else {
             size = 2.0;
         }
@@ -131,24 +389,318 @@ impl MainState {
 
         Ok(())
     }
+
+    /// Builds a mesh of filled shapes of a single color.
+    /// Returns None when there are no shapes.
+    fn build_shape_mesh(
+        ctx: &mut Context,
+        shapes: impl Iterator<Item = Shape>,
+        color: Color,
+    ) -> GameResult<Option<Mesh>> {
+        let mut mesh_builder = graphics::MeshBuilder::new();
+        let mut empty = true;
+        for shape in shapes {
+            empty = false;
+            match shape {
+                Shape::Circle { center, radius } => {
+                    mesh_builder.circle(
+                        DrawMode::fill(),
+                        mint::Point2 {
+                            x: center.x,
+                            y: center.y,
+                        },
+                        radius,
+                        0.5,
+                        color,
+                    )?;
+                }
+                Shape::Rect { center, half_size } => {
+                    let corner = center - half_size;
+                    let rect = graphics::Rect::new(
+                        corner.x,
+                        corner.y,
+                        half_size.x * 2.0,
+                        half_size.y * 2.0,
+                    );
+                    mesh_builder.rectangle(DrawMode::fill(), rect, color)?;
+                }
+            }
+        }
+        if empty {
+            return Ok(None);
+        }
+        Ok(Some(Mesh::from_data(ctx, mesh_builder.build())))
+    }
+
+    /// Builds a mesh of the obstacles at their current positions.
+    /// Returns None when there are no obstacles.
+    fn build_obstacle_mesh(&self, ctx: &mut Context) -> GameResult<Option<Mesh>> {
+        let shapes = self.simulator.obstacles().iter().map(|o| o.shape);
+        Self::build_shape_mesh(ctx, shapes, Color::new(0.4, 0.4, 0.45, 1.0))
+    }
+
+    /// Rebuilds the overlay of the roosts (the timeline may move them).
+    fn rebuild_roost_mesh(&mut self, ctx: &mut Context) -> GameResult<()> {
+        let roost_shapes = self.simulator.roosts().iter().map(|r| Shape::Circle {
+            center: r.pos,
+            radius: r.radius,
+        });
+        self.roost_mesh =
+            Self::build_shape_mesh(ctx, roost_shapes, Color::new(0.6, 0.4, 0.2, 0.15))?;
+        Ok(())
+    }
+
+    /// Advances the simulation by one step and writes the logs.
+    fn advance(&mut self) {
+        self.simulator.update();
+        self.log_new_generations();
+        if self.config.boids_config.startle.is_some() {
+            self.log_finished_cascades();
+        }
+        if !self.config.schedules.is_empty() {
+            self.log_schedules();
+        }
+    }
+
+    /// Runs the simulation without a window for a number of steps, printing the
+    /// readout every READOUT_INTERVAL steps and after the last one.
+    ///
+    /// # Arguments
+    ///
+    /// * `steps` - The number of steps to run.
+    fn run_headless(&mut self, steps: usize) {
+        for step in 1..=steps {
+            self.advance();
+            if self.simulator.take_pause_request() {
+                println!("Step {}: pause ignored (headless run)", step);
+            }
+            if step.is_multiple_of(READOUT_INTERVAL) || step == steps {
+                self.print_stats_readout();
+                if self.config.boids_config.foraging.is_some() {
+                    self.log_food_patches();
+                }
+            }
+        }
+        if self.config.boids_config.foraging.is_some() {
+            self.export_intake();
+        }
+    }
+
+    /// Prints the generations finished since the last call and appends them to the evolution log.
+    fn log_new_generations(&mut self) {
+        let new_generations = &self.simulator.generations[self.logged_generations..];
+        if new_generations.is_empty() {
+            return;
+        }
+        let mut rows = String::new();
+        for generation in new_generations {
+            println!(
+                "Generation {}: population {}, mean fitness {:.1}, avoid {:.3}, matching {:.4}, centering {:.5}, protected {:.1}, visible {:.1}",
+                generation.generation,
+                generation.population,
+                generation.mean_fitness,
+                generation.mean.avoidfactor,
+                generation.mean.matchingfactor,
+                generation.mean.centeringfactor,
+                generation.mean.protected_range,
+                generation.mean.visible_range
+            );
+            rows += &(generation.csv_row() + "\n");
+        }
+        self.logged_generations = self.simulator.generations.len();
+
+        let result = std::fs::OpenOptions::new()
+            .append(true)
+            .open(EVOLUTION_LOG_PATH)
+            .and_then(|mut file| std::io::Write::write_all(&mut file, rows.as_bytes()));
+        if let Err(e) = result {
+            eprintln!("Error writing '{}': {}", EVOLUTION_LOG_PATH, e);
+        }
+    }
+
+    /// Prints the cascades finished since the last call and appends them to the cascade log.
+    fn log_finished_cascades(&mut self) {
+        let finished = self.simulator.take_finished_cascades();
+        if finished.is_empty() {
+            return;
+        }
+        let mut rows = String::new();
+        for cascade in &finished {
+            println!(
+                "Cascade {} ({:?}): reached {} boids over {:.0} px in {} steps ({:.1} px/step)",
+                cascade.id,
+                cascade.trigger,
+                cascade.reach,
+                cascade.max_distance,
+                cascade.last_step - cascade.start_step,
+                cascade.propagation_speed()
+            );
+            rows += &(cascade.csv_row() + "\n");
+        }
+
+        let result = std::fs::OpenOptions::new()
+            .append(true)
+            .open(CASCADE_LOG_PATH)
+            .and_then(|mut file| std::io::Write::write_all(&mut file, rows.as_bytes()));
+        if let Err(e) = result {
+            eprintln!("Error writing '{}': {}", CASCADE_LOG_PATH, e);
+        }
+    }
+
+    /// Prints the state of the food sources and appends it to the food log.
+    fn log_food_patches(&self) {
+        let patches = self.simulator.food_patch_stats();
+        let mut rows = String::new();
+        for patch in &patches {
+            println!(
+                "Food source {}: {:.1} left, {:.1} consumed, {} feeders, depleted for {} steps",
+                patch.patch, patch.amount, patch.consumed, patch.feeders, patch.empty_steps
+            );
+            rows += &(patch.csv_row() + "\n");
+        }
+
+        let result = std::fs::OpenOptions::new()
+            .append(true)
+            .open(FOOD_LOG_PATH)
+            .and_then(|mut file| std::io::Write::write_all(&mut file, rows.as_bytes()));
+        if let Err(e) = result {
+            eprintln!("Error writing '{}': {}", FOOD_LOG_PATH, e);
+        }
+    }
+
+    /// Appends the scheduled parameter values and the polarization of the last step
+    /// to the schedule log.
+    fn log_schedules(&self) {
+        let Some(latest) = self.simulator.latest_stats() else {
+            return;
+        };
+        let mut row = format!("{},{}", latest.step, latest.polarization);
+        for (_, schedule) in &self.config.schedules {
+            row += &format!(",{}", schedule.value_at(latest.step));
+        }
+        row += "\n";
+
+        let result = std::fs::OpenOptions::new()
+            .append(true)
+            .open(SCHEDULE_LOG_PATH)
+            .and_then(|mut file| std::io::Write::write_all(&mut file, row.as_bytes()));
+        if let Err(e) = result {
+            eprintln!("Error writing '{}': {}", SCHEDULE_LOG_PATH, e);
+        }
+    }
+
+    /// Writes the intake of every boid alive to the intake log (overwriting it).
+    fn export_intake(&mut self) {
+        let mut rows = IntakeStats::csv_header() + "\n";
+        for intake in self.simulator.intake_stats() {
+            rows += &(intake.csv_row() + "\n");
+        }
+        match std::fs::write(INTAKE_LOG_PATH, rows) {
+            Ok(()) => println!("Boid intake written to '{}'", INTAKE_LOG_PATH),
+            Err(e) => eprintln!("Error writing '{}': {}", INTAKE_LOG_PATH, e),
+        }
+    }
+
+    /// Prints the current population along with the births and deaths
+    /// since the previous readout, the overlaps and the order of the flock.
+    fn print_stats_readout(&mut self) {
+        let Some(latest) = self.simulator.latest_stats() else {
+            return;
+        };
+        let recent = || self.simulator.history.iter().rev().take(READOUT_INTERVAL);
+        let births: usize = recent().map(|s| s.births).sum();
+        let deaths: usize = recent().map(|s| s.deaths).sum();
+        println!(
+            "Step {}: population {} (+{} births, -{} deaths)",
+            latest.step, latest.population, births, deaths
+        );
+        if self.config.boids_config.collision.is_some() {
+            println!("Overlaps: {}", latest.overlaps);
+        }
+        if self.config.boids_config.startle.is_some() {
+            println!("Alarmed: {}", latest.alarmed);
+        }
+        if self.config.boids_config.formation.is_some() {
+            println!("Formation error: {:.1}", latest.formation_error);
+        }
+        if self.config.behavior.is_some() {
+            let states: Vec<String> = BehaviorState::ALL
+                .iter()
+                .map(|state| format!("{} {}", state.name(), latest.states[state.index()]))
+                .collect();
+            println!("States: {}", states.join(", "));
+        }
+        if let InteractionConfig::Projection { .. } = self.config.boids_config.interaction {
+            println!("Opacity: {:.2}", latest.opacity);
+        }
+        if self.config.boids_config.roosting.is_some() {
+            println!("Roosting: {}", latest.roosting);
+        }
+        if self.config.boids_config.groups.is_some() {
+            println!(
+                "Groups: {}, spread: {:.1}, distance: {:.1}",
+                latest.groups, latest.group_spread, latest.group_distance
+            );
+        }
+        if !self.config.emitters.is_empty() || !self.config.sinks.is_empty() {
+            println!(
+                "Through-flow: emitted {}, absorbed {}",
+                latest.emitted, latest.absorbed
+            );
+        }
+        if self.config.morph.is_some() {
+            println!("Morph error: {:.1}", latest.morph_error);
+        }
+        if !self.config.schedules.is_empty() {
+            let values: Vec<String> = self
+                .config
+                .schedules
+                .iter()
+                .map(|(name, schedule)| format!("{} {:.4}", name, schedule.value_at(latest.step)))
+                .collect();
+            println!("Schedules: {}", values.join(", "));
+        }
+        let polarization = latest.polarization;
+        println!(
+            "Polarization: {:.2}, flocks: {}",
+            polarization,
+            self.simulator.count_flocks()
+        );
+    }
 }
 
 // --- Implement ggez EventHandler trait for MainState ---
 
 impl EventHandler for MainState {
     /// Called to update the game state logic.
+    #[allow(clippy::manual_is_multiple_of)]
     fn update(&mut self, ctx: &mut Context) -> GameResult<()> {
+        if self.paused {
+            return Ok(());
+        }
+
         // Update the simulation state (move boids, etc.)
-        self.simulator.update();
+        self.advance();
+        if self.simulator.take_pause_request() {
+            self.paused = true;
+            println!("Paused by the timeline (press Space to resume)");
+        }
 
         // Rebuild the mesh with the updated boid positions and colors
         // Fix: Correct use of '?' operator
         self.rebuild_boid_mesh(ctx)?;
+        if !self.simulator.roosts().is_empty() {
+            self.rebuild_roost_mesh(ctx)?;
+        }
 
-        // Optional: Print FPS to console
+        // Optional: Print FPS and population to console
         // Fix: Use ctx.time.ticks() and ctx.time.fps()
-        if ctx.time.ticks() % 100 == 0 {
+        if ctx.time.ticks() % READOUT_INTERVAL == 0 {
             println!("FPS: {:.1}", ctx.time.fps());
+            self.print_stats_readout();
+            if self.config.boids_config.foraging.is_some() {
+                self.log_food_patches();
+            }
         }
 
         Ok(())
@@ -172,22 +724,51 @@ impl EventHandler for MainState {
         // When trails are enabled, draw a semi-transparent black rectangle over the entire screen
         if self.show_trails {
             // Create a rectangle covering the entire screen
-            let screen_rect =
-                graphics::Rect::new(0.0, 0.0, self.config.resolution.x, self.config.resolution.y);
+            let screen_rect = graphics::Rect::new(0.0, 0.0, self.world_dims.0, self.world_dims.1);
 
             // Draw a semi-transparent black rectangle
-            // Adjust alpha value to control trail lenght
-            let trails_lenght = self.config.boids_config.trails_len.max(0.0001).min(1.0);
+            // Adjust alpha value to control trail length (the timeline may change it)
+            let trails_length = self.simulator.config().trails_len.clamp(0.0001, 1.0);
             let fade_mesh = Mesh::new_rectangle(
                 ctx,
                 DrawMode::fill(),
                 screen_rect,
-                Color::new(0.0, 0.0, 0.0, trails_lenght),
+                Color::new(0.0, 0.0, 0.0, trails_length),
             )?;
 
             canvas.draw(&fade_mesh, DrawParam::default());
         }
 
+        // --- Draw the World Map ---
+        // Scaled up from one pixel per cell, without smoothing
+        if let Some(image) = &self.map_image {
+            canvas.set_sampler(graphics::Sampler::nearest_clamp());
+            canvas.draw(
+                image,
+                DrawParam::default().scale(Vec2::splat(self.map_cell_size)),
+            );
+            canvas.set_default_sampler();
+        }
+
+        // --- Draw Zones ---
+        if let Some(mesh) = &self.zone_mesh {
+            canvas.draw(mesh, DrawParam::default());
+        }
+        if let Some(mesh) = &self.roost_mesh {
+            canvas.draw(mesh, DrawParam::default());
+        }
+        if let Some(mesh) = &self.emitter_mesh {
+            canvas.draw(mesh, DrawParam::default());
+        }
+        if let Some(mesh) = &self.sink_mesh {
+            canvas.draw(mesh, DrawParam::default());
+        }
+
+        // --- Draw Obstacles ---
+        if let Some(mesh) = self.build_obstacle_mesh(ctx)? {
+            canvas.draw(&mesh, DrawParam::default());
+        }
+
         // --- Draw Boids ---
         // Draw the pre-built mesh if it exists
         if let Some(mesh) = &self.boid_mesh {
@@ -230,10 +811,45 @@ impl EventHandler for MainState {
                     if self.show_trails { "ON" } else { "OFF" }
                 );
             }
+            // Pause or resume the simulation if 'Space' is pressed
+            Some(KeyCode::Space) => {
+                self.paused = !self.paused;
+                println!("{}", if self.paused { "Paused" } else { "Resumed" });
+            }
+            // Startle a random boid if 'S' is pressed (startle only)
+            Some(KeyCode::S) => {
+                self.simulator.startle_random_boid();
+            }
+            // Export the per-boid intake if 'F' is pressed (foraging only)
+            Some(KeyCode::F) if self.config.boids_config.foraging.is_some() => {
+                self.export_intake();
+            }
+            // Toggle coloring by behavioural state if 'B' is pressed (state machine only)
+            Some(KeyCode::B) if self.config.behavior.is_some() => {
+                self.color_by_state = !self.color_by_state;
+            }
+            // Export the pheromone field as an image if 'P' is pressed
+            Some(KeyCode::P) => {
+                if let Some(grid) = self.simulator.pheromone() {
+                    match grid.save_png(PHEROMONE_IMAGE_PATH) {
+                        Ok(()) => println!("Pheromone field written to '{}'", PHEROMONE_IMAGE_PATH),
+                        Err(e) => eprintln!("Error writing '{}': {}", PHEROMONE_IMAGE_PATH, e),
+                    }
+                }
+            }
             _ => {} // Ignore other key presses
         }
         Ok(()) // Return Ok
     }
+
+    /// Called when the application is about to quit.
+    /// Exports the final per-boid intake when foraging is enabled.
+    fn quit_event(&mut self, _ctx: &mut Context) -> GameResult<bool> {
+        if self.config.boids_config.foraging.is_some() {
+            self.export_intake();
+        }
+        Ok(false) // Don't cancel the quit
+    }
 }
 
 // --- Main Function ---
@@ -252,6 +868,77 @@ pub fn main() -> GameResult<()> {
         }
     };
 
+    // Load the scenario, if any: `--scenario <path>` on the command line replaces the
+    // one of the config
+    let args: Vec<String> = std::env::args().collect();
+    let scenario_path = match args.iter().position(|arg| arg == "--scenario") {
+        Some(i) => match args.get(i + 1) {
+            Some(path) => Some(path.clone()),
+            None => {
+                eprintln!("Missing path after --scenario");
+                return Err(ggez::GameError::ResourceLoadError(
+                    "Missing scenario path".to_string(),
+                ));
+            }
+        },
+        None => config.scenario.as_ref().map(|s| s.path.clone()),
+    };
+    // `--headless <steps>` runs the simulation without a window
+    let headless_steps = match args.iter().position(|arg| arg == "--headless") {
+        Some(i) => match args
+            .get(i + 1)
+            .and_then(|steps| steps.parse::<usize>().ok())
+        {
+            Some(steps) => Some(steps),
+            None => {
+                eprintln!("Missing or invalid step count after --headless");
+                return Err(ggez::GameError::ResourceLoadError(
+                    "Invalid headless step count".to_string(),
+                ));
+            }
+        },
+        None => None,
+    };
+    let scenario = match &scenario_path {
+        Some(path) => match Scenario::load(path) {
+            Ok(scenario) => Some(scenario),
+            Err(e) => {
+                eprintln!("Error loading scenario from '{}': {}", path, e);
+                return Err(ggez::GameError::ResourceLoadError(format!(
+                    "Failed to load scenario: {}",
+                    e
+                )));
+            }
+        },
+        None => None,
+    };
+
+    // Load the world map, if any: its size replaces the configured resolution
+    let world_map = match &config.map {
+        Some(map_config) => match WorldMap::load(map_config) {
+            Ok(map) => Some(map),
+            Err(e) => {
+                eprintln!("Error loading world map from '{}': {}", map_config.path, e);
+                return Err(ggez::GameError::ResourceLoadError(format!(
+                    "Failed to load world map: {}",
+                    e
+                )));
+            }
+        },
+        None => None,
+    };
+    // --- Headless run: no window, a fixed number of steps ---
+    if let Some(steps) = headless_steps {
+        let mut state = MainState::new_headless(config, world_map, scenario)?;
+        state.run_headless(steps);
+        return Ok(());
+    }
+
+    let (width, height) = match &world_map {
+        Some(map) => map.world_dims(),
+        None => (config.resolution.x, config.resolution.y),
+    };
+
     // --- Build ggez context and window ---
     let (mut ctx, event_loop) = ContextBuilder::new("boids_simulation", "Dakube")
         // Configure window settings based on loaded config
@@ -260,7 +947,7 @@ pub fn main() -> GameResult<()> {
         )
         .window_mode(
             WindowMode::default()
-                .dimensions(config.resolution.x, config.resolution.y)
+                .dimensions(width, height)
                 .resizable(false) // Keep window non-resizable for simplicity
                 .borderless(true), // Set to true to mimic pygame.NOFRAME (might affect positioning)
         )
@@ -270,6 +957,6 @@ pub fn main() -> GameResult<()> {
         winit::dpi::PhysicalPosition::new(config.position.x as f32, config.position.y as f32);
     ctx.gfx.set_window_position(window_pos)?;
     // --- Create and run the main state ---
-    let state = MainState::new(&mut ctx, config)?;
+    let state = MainState::new(&mut ctx, config, world_map, scenario)?;
     event::run(ctx, event_loop, state) // Start the ggez event loop
 }
@@ -0,0 +1,702 @@
+// src/morph.rs
+// Shape morphing for drone-show previews: boids fly to points sampled from a
+// timeline of target shapes (image, SVG path or text), assigned with minimum
+// total travel by an auction algorithm.
+
+use std::collections::{HashMap, HashSet};
+
+use ggez::glam::Vec2;
+use uuid::Uuid;
+
+use crate::boids::Boid;
+use crate::config::{MorphConfig, MorphSourceConfig};
+use crate::font;
+
+// --- TargetShape Enum Definition ---
+
+#[derive(Debug, Clone)]
+pub enum TargetShape {
+    // Filled pixels, sampled on a regular lattice
+    Area {
+        mask: Vec<bool>, // row-major, true for filled pixels
+        width: usize,
+        height: usize,
+    },
+    // Polylines, sampled evenly along their length
+    Path {
+        polylines: Vec<Vec<Vec2>>,
+    },
+}
+
+// --- TargetShape Implementation ---
+
+impl TargetShape {
+    /// Loads a target shape from its source.
+    ///
+    /// # Arguments
+    ///
+    /// * 'source' - The MorphSourceConfig read from boids.yaml.
+    ///
+    /// # Returns
+    ///
+    /// * Result<TargetShape, Box<dyn std::error::Error>> - The shape, or an error if
+    ///   the file cannot be read or has nothing to sample.
+    pub fn load(source: &MorphSourceConfig) -> Result<TargetShape, Box<dyn std::error::Error>> {
+        let shape = match source {
+            MorphSourceConfig::Image { path } => {
+                let image = image::open(path)?.to_rgba8();
+                // Opaque pixels on a transparent background, dark pixels otherwise
+                let transparent = image.pixels().any(|pixel| pixel.0[3] < 128);
+                let mask = image
+                    .pixels()
+                    .map(|pixel| {
+                        let [r, g, b, a] = pixel.0;
+                        let luma = 0.299 * r as f32 + 0.587 * g as f32 + 0.114 * b as f32;
+                        a >= 128 && (transparent || luma < 128.0)
+                    })
+                    .collect();
+                TargetShape::Area {
+                    mask,
+                    width: image.width() as usize,
+                    height: image.height() as usize,
+                }
+            }
+            MorphSourceConfig::Svg { path } => TargetShape::Path {
+                polylines: parse_svg_paths(&std::fs::read_to_string(path)?),
+            },
+            MorphSourceConfig::Text { text } => {
+                let (mask, width, height) = font::render_text(text);
+                TargetShape::Area {
+                    mask,
+                    width,
+                    height,
+                }
+            }
+        };
+        if shape.bounds().is_none() {
+            return Err(format!("nothing to sample in {:?}", source).into());
+        }
+        Ok(shape)
+    }
+
+    /// Returns the bounding box (min, max) of the shape, or None if it is empty.
+    fn bounds(&self) -> Option<(Vec2, Vec2)> {
+        let mut points: Box<dyn Iterator<Item = (Vec2, Vec2)>> = match self {
+            TargetShape::Area { mask, width, .. } => {
+                let width = *width;
+                Box::new(mask.iter().enumerate().filter(|(_, filled)| **filled).map(
+                    move |(i, _)| {
+                        let corner = Vec2::new((i % width) as f32, (i / width) as f32);
+                        (corner, corner + Vec2::ONE)
+                    },
+                ))
+            }
+            TargetShape::Path { polylines } => {
+                Box::new(polylines.iter().flatten().map(|&point| (point, point)))
+            }
+        };
+        let first = points.next()?;
+        Some(points.fold(first, |(min, max), (a, b)| (min.min(a), max.max(b))))
+    }
+
+    /// Samples evenly spread points of the shape, in shape coordinates.
+    ///
+    /// # Arguments
+    ///
+    /// * 'count' - The number of points.
+    pub fn sample(&self, count: usize) -> Vec<Vec2> {
+        if count == 0 {
+            return Vec::new();
+        }
+        match self {
+            TargetShape::Area {
+                mask,
+                width,
+                height,
+            } => {
+                // Points of a lattice of the given spacing that fall in filled pixels
+                let lattice = |spacing: f32| -> Vec<Vec2> {
+                    let mut points = Vec::new();
+                    let mut y = spacing * 0.5;
+                    while y < *height as f32 {
+                        let mut x = spacing * 0.5;
+                        while x < *width as f32 {
+                            if mask[y as usize * width + x as usize] {
+                                points.push(Vec2::new(x, y));
+                            }
+                            x += spacing;
+                        }
+                        y += spacing;
+                    }
+                    points
+                };
+                // Largest spacing giving enough points (bisection), then keep count of them
+                let filled = mask.iter().filter(|filled| **filled).count() as f32;
+                let (mut low, mut high) = (0.05, (filled / count as f32).sqrt() * 2.0 + 1.0);
+                for _ in 0..30 {
+                    let mid = (low + high) * 0.5;
+                    if lattice(mid).len() >= count {
+                        low = mid;
+                    } else {
+                        high = mid;
+                    }
+                }
+                let points = lattice(low);
+                (0..count)
+                    .map(|k| points[k * points.len() / count])
+                    .collect()
+            }
+            TargetShape::Path { polylines } => {
+                // Points at regular intervals along the total length
+                let segments: Vec<(Vec2, Vec2)> = polylines
+                    .iter()
+                    .flat_map(|line| line.windows(2).map(|w| (w[0], w[1])))
+                    .collect();
+                let total: f32 = segments.iter().map(|(a, b)| a.distance(*b)).sum();
+                if segments.is_empty() || total <= 0.0 {
+                    let point = polylines
+                        .iter()
+                        .flatten()
+                        .next()
+                        .copied()
+                        .unwrap_or(Vec2::ZERO);
+                    return vec![point; count];
+                }
+                let step = total / count as f32;
+                let mut points = Vec::with_capacity(count);
+                let (mut segment, mut walked) = (0, 0.0);
+                for k in 0..count {
+                    let distance = (k as f32 + 0.5) * step;
+                    while segment + 1 < segments.len()
+                        && walked + segments[segment].0.distance(segments[segment].1) < distance
+                    {
+                        walked += segments[segment].0.distance(segments[segment].1);
+                        segment += 1;
+                    }
+                    let (a, b) = segments[segment];
+                    let length = a.distance(b);
+                    let t = if length > 0.0 {
+                        ((distance - walked) / length).clamp(0.0, 1.0)
+                    } else {
+                        0.0
+                    };
+                    points.push(a.lerp(b, t));
+                }
+                points
+            }
+        }
+    }
+}
+
+// --- MorphStage Struct Definition ---
+
+#[derive(Debug, Clone)]
+struct MorphStage {
+    shape: TargetShape,
+    center: Vec2, // world position of the center of the shape
+    width: f32,   // world width of the shape
+    steps: u64,   // duration of the stage
+}
+
+// --- Morph Struct Definition ---
+
+#[derive(Debug, Clone)]
+pub struct Morph {
+    stages: Vec<MorphStage>,
+    factor: f32,
+    slowing_distance: f32,
+    looped: bool,
+    current: usize,                // index of the current stage
+    stage_start: u64,              // step the current stage started at
+    points: Vec<Vec2>,             // target points of the current stage
+    targets: HashMap<Uuid, usize>, // index of the target point of every boid
+    assigned: Option<usize>,       // stage the targets were assigned for
+}
+
+// --- Morph Implementation ---
+
+impl Morph {
+    /// Creates the morph timeline, loading the shape of every stage.
+    ///
+    /// # Arguments
+    ///
+    /// * 'config' - The MorphConfig read from boids.yaml.
+    ///
+    /// # Returns
+    ///
+    /// * Result<Morph, Box<dyn std::error::Error>> - The morph, or an error if a shape
+    ///   cannot be loaded.
+    pub fn new(config: &MorphConfig) -> Result<Morph, Box<dyn std::error::Error>> {
+        let stages = config
+            .stages
+            .iter()
+            .map(|stage| {
+                Ok(MorphStage {
+                    shape: TargetShape::load(&stage.source)?,
+                    center: Vec2::new(stage.x, stage.y),
+                    width: stage.width,
+                    steps: stage.steps,
+                })
+            })
+            .collect::<Result<Vec<_>, Box<dyn std::error::Error>>>()?;
+        Ok(Morph {
+            stages,
+            factor: config.factor,
+            slowing_distance: config.slowing_distance,
+            looped: config.looped,
+            current: 0,
+            stage_start: 0,
+            points: Vec::new(),
+            targets: HashMap::new(),
+            assigned: None,
+        })
+    }
+
+    /// Advances the timeline and assigns the targets of the current stage when the
+    /// stage changes. When boids appear or disappear, only the new boids are given
+    /// free targets, the full assignment being redone at the next stage change.
+    ///
+    /// # Arguments
+    ///
+    /// * 'step' - The current simulation step.
+    /// * 'boids' - A slice of all the boids of the simulation.
+    pub fn update(&mut self, step: u64, boids: &[Boid]) {
+        if self.stages.is_empty() {
+            return;
+        }
+        if step - self.stage_start >= self.stages[self.current].steps {
+            if self.current + 1 < self.stages.len() {
+                self.current += 1;
+                self.stage_start = step;
+            } else if self.looped {
+                self.current = 0;
+                self.stage_start = step;
+            }
+        }
+        if self.assigned != Some(self.current) {
+            self.assign_targets(boids);
+        } else if self.targets.len() != boids.len()
+            || boids
+                .iter()
+                .any(|boid| !self.targets.contains_key(&boid.id))
+        {
+            self.assign_new_boids(boids);
+        }
+    }
+
+    /// Samples points of the current shape, placed and scaled in the world.
+    fn stage_points(&self, count: usize) -> Vec<Vec2> {
+        let stage = &self.stages[self.current];
+        let Some((min, max)) = stage.shape.bounds() else {
+            return Vec::new();
+        };
+        let scale = stage.width / (max.x - min.x).max(1e-6);
+        let mid = (min + max) * 0.5;
+        stage
+            .shape
+            .sample(count)
+            .into_iter()
+            .map(|point| stage.center + (point - mid) * scale)
+            .collect()
+    }
+
+    /// Samples one point of the current shape per boid and assigns them with minimum
+    /// total travel distance.
+    fn assign_targets(&mut self, boids: &[Boid]) {
+        let points = self.stage_points(boids.len());
+        if points.is_empty() {
+            return;
+        }
+
+        let n = boids.len();
+        let mut cost = Vec::with_capacity(n * n);
+        for boid in boids {
+            cost.extend(points.iter().map(|point| boid.pos.distance(*point)));
+        }
+        let assignment = auction_assignment(&cost, n);
+        self.targets = boids.iter().map(|boid| boid.id).zip(assignment).collect();
+        self.points = points;
+        self.assigned = Some(self.current);
+    }
+
+    /// Releases the targets of the boids gone and gives each new boid the closest
+    /// free target, sampling extra points when there are not enough free ones.
+    fn assign_new_boids(&mut self, boids: &[Boid]) {
+        let alive: HashSet<Uuid> = boids.iter().map(|boid| boid.id).collect();
+        self.targets.retain(|id, _| alive.contains(id));
+        let mut taken = vec![false; self.points.len()];
+        for &j in self.targets.values() {
+            taken[j] = true;
+        }
+
+        let new_boids: Vec<&Boid> = boids
+            .iter()
+            .filter(|boid| !self.targets.contains_key(&boid.id))
+            .collect();
+        let free = taken.iter().filter(|&&t| !t).count();
+        if new_boids.len() > free {
+            let extra = self.stage_points(new_boids.len() - free);
+            self.points.extend(extra);
+            taken.resize(self.points.len(), false);
+        }
+
+        for boid in new_boids {
+            let closest = (0..self.points.len())
+                .filter(|&j| !taken[j])
+                .min_by(|&a, &b| {
+                    let da = boid.pos.distance_squared(self.points[a]);
+                    let db = boid.pos.distance_squared(self.points[b]);
+                    da.total_cmp(&db)
+                });
+            let Some(j) = closest else {
+                break; // The shape has no points
+            };
+            taken[j] = true;
+            self.targets.insert(boid.id, j);
+        }
+    }
+
+    /// Returns the target point of a boid, if assigned.
+    pub fn target_of(&self, id: &Uuid) -> Option<Vec2> {
+        self.targets.get(id).map(|&j| self.points[j])
+    }
+
+    /// Calculates the velocity change bringing a boid to its target: full speed far
+    /// away, slowing down to a stop within the slowing distance.
+    ///
+    /// # Arguments
+    ///
+    /// * 'boid' - The boid.
+    /// * 'target' - Its target point.
+    /// * 'maxspeed' - The speed limit.
+    ///
+    /// # Returns
+    ///
+    /// * 'Vec2' - The arrival velocity change.
+    pub fn velocity_change(&self, boid: &Boid, target: Vec2, maxspeed: f32) -> Vec2 {
+        let to_target = target - boid.pos;
+        let distance = to_target.length();
+        let speed = maxspeed * (distance / self.slowing_distance.max(1e-6)).min(1.0);
+        let desired = to_target.normalize_or_zero() * speed;
+        (desired - boid.vel) * self.factor
+    }
+}
+
+/// Solves the assignment problem (each bidder gets one object, minimizing the total
+/// cost) with the auction algorithm and epsilon scaling. The result is within
+/// n * epsilon of the optimum, with a final epsilon of 1% of the largest cost over n.
+///
+/// # Arguments
+///
+/// * 'cost' - The n x n row-major cost matrix (bidder, object).
+/// * 'n' - The number of bidders and objects.
+///
+/// # Returns
+///
+/// * 'Vec<usize>' - The object assigned to each bidder.
+pub fn auction_assignment(cost: &[f32], n: usize) -> Vec<usize> {
+    if n == 0 {
+        return Vec::new();
+    }
+    let max_cost = cost.iter().copied().fold(0.0, f32::max) as f64;
+    let eps_min = (max_cost * 0.01 / n as f64).max(1e-9);
+    let mut eps = (max_cost * 0.25).max(eps_min);
+    let mut prices = vec![0.0f64; n];
+    loop {
+        // Every bidder bids for its best object, outbidding its current owner
+        let mut owner = vec![usize::MAX; n];
+        let mut assigned = vec![usize::MAX; n];
+        let mut unassigned: Vec<usize> = (0..n).rev().collect();
+        while let Some(i) = unassigned.pop() {
+            let row = &cost[i * n..(i + 1) * n];
+            let (mut best, mut best_value, mut second_value) = (0, f64::MIN, f64::MIN);
+            for (j, &c) in row.iter().enumerate() {
+                let value = -(c as f64) - prices[j];
+                if value > best_value {
+                    second_value = best_value;
+                    best_value = value;
+                    best = j;
+                } else if value > second_value {
+                    second_value = value;
+                }
+            }
+            let increment = if n > 1 {
+                best_value - second_value + eps
+            } else {
+                eps
+            };
+            prices[best] += increment;
+            if owner[best] != usize::MAX {
+                assigned[owner[best]] = usize::MAX;
+                unassigned.push(owner[best]);
+            }
+            owner[best] = i;
+            assigned[i] = best;
+        }
+        if eps <= eps_min {
+            return assigned;
+        }
+        eps = (eps / 5.0).max(eps_min);
+    }
+}
+
+/// Extracts the outlines of the `d` attributes of the paths of an SVG document as
+/// polylines. Supports the M, L, H, V, C, Q and Z commands (absolute and relative),
+/// curves being flattened into line segments.
+///
+/// # Arguments
+///
+/// * 'svg' - The SVG document.
+///
+/// # Returns
+///
+/// * 'Vec<Vec<Vec2>>' - One polyline per subpath.
+pub fn parse_svg_paths(svg: &str) -> Vec<Vec<Vec2>> {
+    let mut polylines = Vec::new();
+    let mut rest = svg;
+    while let Some(start) = rest.find("<path") {
+        rest = &rest[start + 5..];
+        let tag = &rest[..rest.find('>').unwrap_or(rest.len())];
+        // The d attribute, with either kind of quotes
+        let Some(d) = [" d=\"", " d='"].iter().find_map(|prefix| {
+            let begin = tag.find(prefix)? + prefix.len();
+            let quote = prefix.chars().last()?;
+            let end = tag[begin..].find(quote)? + begin;
+            Some(&tag[begin..end])
+        }) else {
+            continue;
+        };
+        polylines.extend(parse_path_data(d));
+    }
+    polylines
+}
+
+// A token of SVG path data
+#[derive(Debug, Clone, Copy)]
+enum PathToken {
+    Command(char),
+    Number(f32),
+}
+
+/// Splits SVG path data into commands and numbers.
+fn tokenize_path_data(d: &str) -> Vec<PathToken> {
+    let mut tokens = Vec::new();
+    let chars: Vec<char> = d.chars().collect();
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        if c.is_ascii_alphabetic() && c != 'e' && c != 'E' {
+            tokens.push(PathToken::Command(c));
+            i += 1;
+        } else if c.is_ascii_digit() || c == '-' || c == '+' || c == '.' {
+            // Sign, digits with at most one dot, optional exponent
+            let start = i;
+            let (mut dot, mut exponent) = (c == '.', false);
+            i += 1;
+            while i < chars.len() {
+                let c = chars[i];
+                if c.is_ascii_digit() {
+                    i += 1;
+                } else if c == '.' && !dot && !exponent {
+                    dot = true;
+                    i += 1;
+                } else if (c == 'e' || c == 'E') && !exponent {
+                    exponent = true;
+                    i += 1;
+                    if i < chars.len() && (chars[i] == '-' || chars[i] == '+') {
+                        i += 1;
+                    }
+                } else {
+                    break;
+                }
+            }
+            let text: String = chars[start..i].iter().collect();
+            if let Ok(number) = text.parse() {
+                tokens.push(PathToken::Number(number));
+            }
+        } else {
+            i += 1; // Separator
+        }
+    }
+    tokens
+}
+
+/// Interprets SVG path data as polylines (see parse_svg_paths).
+fn parse_path_data(d: &str) -> Vec<Vec<Vec2>> {
+    const CURVE_SEGMENTS: usize = 16;
+    let tokens = tokenize_path_data(d);
+    let mut polylines: Vec<Vec<Vec2>> = Vec::new();
+    let mut line: Vec<Vec2> = Vec::new();
+    let (mut pos, mut start) = (Vec2::ZERO, Vec2::ZERO);
+    let mut command = 'M';
+    let mut i = 0;
+
+    // Reads the next n numbers, None if the data runs out or a command comes first
+    let read = |i: &mut usize, count: usize| -> Option<Vec<f32>> {
+        let mut numbers = Vec::with_capacity(count);
+        for _ in 0..count {
+            match tokens.get(*i) {
+                Some(PathToken::Number(number)) => numbers.push(*number),
+                _ => return None,
+            }
+            *i += 1;
+        }
+        Some(numbers)
+    };
+
+    while i < tokens.len() {
+        if let PathToken::Command(c) = tokens[i] {
+            command = c;
+            i += 1;
+            if c == 'Z' || c == 'z' {
+                if !line.is_empty() {
+                    line.push(start);
+                    polylines.push(std::mem::take(&mut line));
+                }
+                pos = start;
+                continue;
+            }
+        }
+        let relative = command.is_ascii_lowercase();
+        let origin = if relative { pos } else { Vec2::ZERO };
+        match command.to_ascii_uppercase() {
+            'M' => {
+                let Some(n) = read(&mut i, 2) else { break };
+                if line.len() > 1 {
+                    polylines.push(std::mem::take(&mut line));
+                }
+                line.clear();
+                pos = origin + Vec2::new(n[0], n[1]);
+                start = pos;
+                line.push(pos);
+                // Further coordinate pairs are line segments
+                command = if relative { 'l' } else { 'L' };
+            }
+            'L' => {
+                let Some(n) = read(&mut i, 2) else { break };
+                pos = origin + Vec2::new(n[0], n[1]);
+                line.push(pos);
+            }
+            'H' => {
+                let Some(n) = read(&mut i, 1) else { break };
+                pos.x = if relative { pos.x + n[0] } else { n[0] };
+                line.push(pos);
+            }
+            'V' => {
+                let Some(n) = read(&mut i, 1) else { break };
+                pos.y = if relative { pos.y + n[0] } else { n[0] };
+                line.push(pos);
+            }
+            'C' => {
+                let Some(n) = read(&mut i, 6) else { break };
+                let (c1, c2) = (
+                    origin + Vec2::new(n[0], n[1]),
+                    origin + Vec2::new(n[2], n[3]),
+                );
+                let end = origin + Vec2::new(n[4], n[5]);
+                for k in 1..=CURVE_SEGMENTS {
+                    let t = k as f32 / CURVE_SEGMENTS as f32;
+                    let u = 1.0 - t;
+                    line.push(
+                        pos * (u * u * u)
+                            + c1 * (3.0 * u * u * t)
+                            + c2 * (3.0 * u * t * t)
+                            + end * (t * t * t),
+                    );
+                }
+                pos = end;
+            }
+            'Q' => {
+                let Some(n) = read(&mut i, 4) else { break };
+                let (c, end) = (
+                    origin + Vec2::new(n[0], n[1]),
+                    origin + Vec2::new(n[2], n[3]),
+                );
+                for k in 1..=CURVE_SEGMENTS {
+                    let t = k as f32 / CURVE_SEGMENTS as f32;
+                    let u = 1.0 - t;
+                    line.push(pos * (u * u) + c * (2.0 * u * t) + end * (t * t));
+                }
+                pos = end;
+            }
+            _ => i += 1, // Unsupported command: skip its numbers
+        }
+    }
+    if line.len() > 1 {
+        polylines.push(line);
+    }
+    polylines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+
+    /// Total cost of an assignment.
+    fn total(cost: &[f32], n: usize, assignment: &[usize]) -> f32 {
+        assignment
+            .iter()
+            .enumerate()
+            .map(|(i, &j)| cost[i * n + j])
+            .sum()
+    }
+
+    #[test]
+    fn auction_gives_a_permutation() {
+        let n = 40;
+        let mut rng = StdRng::seed_from_u64(3);
+        let cost: Vec<f32> = (0..n * n).map(|_| rng.random::<f32>() * 100.0).collect();
+        let mut assignment = auction_assignment(&cost, n);
+        assignment.sort_unstable();
+        assert_eq!(assignment, (0..n).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn auction_is_optimal_on_a_small_matrix() {
+        // Greedy row by row takes 1 + 4 + 9 = 14, the optimum is 1 + 9 + 3 = 13
+        #[rustfmt::skip]
+        let cost = [
+            1.0, 2.0, 8.0,
+            3.0, 4.0, 9.0,
+            9.0, 3.0, 9.0,
+        ];
+        let assignment = auction_assignment(&cost, 3);
+        assert_eq!(assignment, vec![0, 2, 1]);
+        assert_eq!(total(&cost, 3, &assignment), 13.0);
+    }
+
+    /// Smallest total cost over all the permutations (brute force).
+    fn brute_force_optimum(cost: &[f32], n: usize) -> f32 {
+        fn search(cost: &[f32], n: usize, row: usize, used: &mut [bool], sum: f32) -> f32 {
+            if row == n {
+                return sum;
+            }
+            let mut best = f32::INFINITY;
+            for j in 0..n {
+                if !used[j] {
+                    used[j] = true;
+                    best = best.min(search(cost, n, row + 1, used, sum + cost[row * n + j]));
+                    used[j] = false;
+                }
+            }
+            best
+        }
+        search(cost, n, 0, &mut vec![false; n], 0.0)
+    }
+
+    #[test]
+    fn auction_matches_brute_force_on_integer_costs() {
+        // With integer costs up to 50, n * epsilon stays below 1: the result is exact
+        let n = 6;
+        let mut rng = StdRng::seed_from_u64(11);
+        for _ in 0..20 {
+            let cost: Vec<f32> = (0..n * n)
+                .map(|_| rng.random_range(0..=50) as f32)
+                .collect();
+            let assignment = auction_assignment(&cost, n);
+            assert_eq!(total(&cost, n, &assignment), brute_force_optimum(&cost, n));
+        }
+    }
+}
@@ -0,0 +1,504 @@
+// src/obstacles.rs
+// Obstacles boids steer around, either static or moving along scripted
+// trajectories (linear, circular or through waypoints).
+
+use ggez::glam::Vec2;
+
+use crate::boids::Boid;
+use crate::config::{ObstacleAvoidanceConfig, ObstacleConfig, RaycastConfig, TrajectoryConfig};
+use crate::shapes::Shape;
+use crate::world_map::WorldMap;
+
+// --- Trajectory Enum Definition ---
+
+#[derive(Debug, Clone)]
+pub enum Trajectory {
+    Static,
+    Linear {
+        vel: Vec2,
+        wrap: bool,
+    },
+    Circular {
+        center: Vec2,
+        radius: f32,
+        angle: f32,
+        angular_speed: f32,
+    },
+    Waypoints {
+        points: Vec<Vec2>,
+        speed: f32,
+        looped: bool,
+        target: usize,
+    },
+}
+
+// --- Obstacle Struct Definition ---
+
+#[derive(Debug, Clone)]
+pub struct Obstacle {
+    pub shape: Shape,
+    pub vel: Vec2, // displacement per unit of time during the last step
+    trajectory: Trajectory,
+}
+
+// --- Obstacle Implementation ---
+
+impl Obstacle {
+    /// Creates a new Obstacle from its configuration.
+    ///
+    /// # Arguments
+    ///
+    /// * 'config' - The ObstacleConfig read from boids.yaml.
+    ///
+    /// # Returns
+    ///
+    /// * 'Self' - A new Obstacle instance.
+    pub fn new(config: &ObstacleConfig) -> Self {
+        let shape = Shape::new(&config.shape);
+        let start = shape.center();
+        let trajectory = match &config.trajectory {
+            TrajectoryConfig::Static => Trajectory::Static,
+            TrajectoryConfig::Linear { vx, vy, wrap } => Trajectory::Linear {
+                vel: Vec2::new(*vx, *vy),
+                wrap: *wrap,
+            },
+            TrajectoryConfig::Circular {
+                center_x,
+                center_y,
+                angular_speed,
+            } => {
+                // The orbit goes through the starting position of the obstacle
+                let center = Vec2::new(*center_x, *center_y);
+                let offset = start - center;
+                Trajectory::Circular {
+                    center,
+                    radius: offset.length(),
+                    angle: offset.y.atan2(offset.x),
+                    angular_speed: *angular_speed,
+                }
+            }
+            TrajectoryConfig::Waypoints {
+                points,
+                speed,
+                looped,
+            } => Trajectory::Waypoints {
+                points: points.iter().map(|p| Vec2::new(p.x, p.y)).collect(),
+                speed: *speed,
+                looped: *looped,
+                target: 0,
+            },
+        };
+
+        Obstacle {
+            shape,
+            vel: Vec2::ZERO,
+            trajectory,
+        }
+    }
+
+    /// Moves the obstacle along its trajectory for one step.
+    ///
+    /// # Arguments
+    ///
+    /// * 'dt' - Duration of the step (the config dt).
+    /// * 'world_dims' - A tuple (width, height) used to wrap linear trajectories.
+    pub fn update(&mut self, dt: f32, world_dims: (f32, f32)) {
+        let old_pos = self.shape.center();
+        let new_pos = match &mut self.trajectory {
+            Trajectory::Static => old_pos,
+            Trajectory::Linear { vel, wrap } => {
+                let mut pos = old_pos + *vel * dt;
+                if *wrap {
+                    pos.x = pos.x.rem_euclid(world_dims.0);
+                    pos.y = pos.y.rem_euclid(world_dims.1);
+                }
+                pos
+            }
+            Trajectory::Circular {
+                center,
+                radius,
+                angle,
+                angular_speed,
+            } => {
+                *angle += *angular_speed * dt;
+                *center + Vec2::new(angle.cos(), angle.sin()) * *radius
+            }
+            Trajectory::Waypoints {
+                points,
+                speed,
+                looped,
+                target,
+            } => {
+                // Walk towards the current waypoint, moving on to the next ones if reached
+                let mut pos = old_pos;
+                let mut remaining = *speed * dt;
+                while *target < points.len() && remaining > 0.0 {
+                    let to_target = points[*target] - pos;
+                    let dist = to_target.length();
+                    if dist > remaining {
+                        pos += to_target / dist * remaining;
+                        break;
+                    }
+                    pos = points[*target];
+                    remaining -= dist;
+                    *target += 1;
+                    if *target == points.len() && *looped {
+                        *target = 0;
+                    }
+                    if dist < 1e-6 && *target == 0 {
+                        break; // Degenerate loop on a single point
+                    }
+                }
+                pos
+            }
+        };
+
+        // Velocity from the displacement (wrapping teleports are not motion)
+        let displacement = new_pos - old_pos;
+        self.vel = match self.trajectory {
+            Trajectory::Linear { vel, wrap: true } => vel,
+            _ if dt > 1e-6 => displacement / dt,
+            _ => Vec2::ZERO,
+        };
+        self.shape.set_center(new_pos);
+    }
+
+    /// Pushes a boid that ended inside the obstacle back onto its boundary and
+    /// removes the part of its velocity going into the obstacle.
+    ///
+    /// # Arguments
+    ///
+    /// * 'boid' - The boid to push out.
+    pub fn push_out(&self, boid: &mut Boid) {
+        let dist = self.shape.signed_distance(boid.pos);
+        if dist >= 0.0 {
+            return;
+        }
+        let normal = self.shape.normal(boid.pos);
+        boid.pos += normal * -dist;
+        let inward = (boid.vel - self.vel).dot(normal);
+        if inward < 0.0 {
+            boid.vel -= normal * inward;
+        }
+    }
+}
+
+// --- ObstacleGrid Struct Definition ---
+
+// Uniform grid over the world listing, for each cell, the obstacles whose bounding
+// box overlaps it. Keeps line of sight tests cheap: a segment only needs to be
+// tested against the obstacles of the cells it crosses.
+#[derive(Debug, Clone, Default)]
+pub struct ObstacleGrid {
+    cell_size: f32,
+    cols: usize,
+    rows: usize,
+    cells: Vec<Vec<usize>>, // obstacle indices per cell, row-major
+}
+
+// --- ObstacleGrid Implementation ---
+
+impl ObstacleGrid {
+    /// Builds the grid for the obstacles at their current positions.
+    ///
+    /// # Arguments
+    ///
+    /// * 'obstacles' - A slice of all the obstacles of the simulation.
+    /// * 'world_dims' - A tuple (width, height) of the simulation area.
+    /// * 'cell_size' - Side of a grid cell (typically the visible range).
+    ///
+    /// # Returns
+    ///
+    /// * 'Self' - A new ObstacleGrid instance.
+    pub fn new(obstacles: &[Obstacle], world_dims: (f32, f32), cell_size: f32) -> Self {
+        let cell_size = cell_size.max(1.0);
+        let cols = (world_dims.0 / cell_size).ceil().max(1.0) as usize;
+        let rows = (world_dims.1 / cell_size).ceil().max(1.0) as usize;
+        let mut grid = ObstacleGrid {
+            cell_size,
+            cols,
+            rows,
+            cells: vec![Vec::new(); cols * rows],
+        };
+        for (i, obstacle) in obstacles.iter().enumerate() {
+            let (min, max) = obstacle.shape.bounds();
+            let (c0, r0) = grid.cell_of(min);
+            let (c1, r1) = grid.cell_of(max);
+            for r in r0..=r1 {
+                for c in c0..=c1 {
+                    grid.cells[r * cols + c].push(i);
+                }
+            }
+        }
+        grid
+    }
+
+    /// Returns the (column, row) of the cell containing a point, clamped to the grid.
+    fn cell_of(&self, p: Vec2) -> (usize, usize) {
+        let c = (p.x / self.cell_size)
+            .floor()
+            .clamp(0.0, (self.cols - 1) as f32);
+        let r = (p.y / self.cell_size)
+            .floor()
+            .clamp(0.0, (self.rows - 1) as f32);
+        (c as usize, r as usize)
+    }
+
+    /// Returns true if no obstacle intersects the segment from a to b.
+    ///
+    /// # Arguments
+    ///
+    /// * 'obstacles' - The obstacles the grid was built from.
+    /// * 'a', 'b' - The end points of the segment.
+    pub fn line_of_sight(&self, obstacles: &[Obstacle], a: Vec2, b: Vec2) -> bool {
+        // Cells overlapped by the bounding box of the segment (segments are short)
+        let (c0, r0) = self.cell_of(a.min(b));
+        let (c1, r1) = self.cell_of(a.max(b));
+        let mut tested: Vec<usize> = Vec::new();
+        for r in r0..=r1 {
+            for c in c0..=c1 {
+                for &i in &self.cells[r * self.cols + c] {
+                    if tested.contains(&i) {
+                        continue;
+                    }
+                    tested.push(i);
+                    if obstacles[i].shape.intersects_segment(a, b) {
+                        return false;
+                    }
+                }
+            }
+        }
+        true
+    }
+}
+
+/// Calculates the velocity change steering a boid away from the obstacles.
+/// With raycast vision enabled, the boid steers towards the clearest direction of its
+/// fan of rays instead (see raycast_velocity_change), the rays also stopping at the
+/// walls of the world map. Otherwise the distance is
+/// measured both at the current position and at the position predicted from the
+/// motion relative to each obstacle, so that boids dodge obstacles moving towards
+/// them early.
+///
+/// # Arguments
+///
+/// * 'boid' - The boid avoiding the obstacles.
+/// * 'obstacles' - A slice of all the obstacles of the simulation.
+/// * 'world_map' - The world map whose walls block the rays, if any.
+/// * 'params' - The ObstacleAvoidanceConfig parameters.
+/// * 'dt' - Duration of one step (the config dt).
+///
+/// # Returns
+///
+/// * 'Vec2' - The avoidance velocity change.
+pub fn avoidance_velocity_change(
+    boid: &Boid,
+    obstacles: &[Obstacle],
+    world_map: Option<&WorldMap>,
+    params: &ObstacleAvoidanceConfig,
+    dt: f32,
+) -> Vec2 {
+    if let Some(raycast) = &params.raycast {
+        return raycast_velocity_change(boid, obstacles, world_map, raycast);
+    }
+
+    let mut delta_v = Vec2::ZERO;
+    for obstacle in obstacles {
+        let relative_vel = boid.vel - obstacle.vel;
+        let predicted = boid.pos + relative_vel * params.lookahead * dt;
+
+        let dist_now = obstacle.shape.signed_distance(boid.pos);
+        let dist_predicted = obstacle.shape.signed_distance(predicted);
+        let (dist, point) = if dist_predicted < dist_now {
+            (dist_predicted, predicted)
+        } else {
+            (dist_now, boid.pos)
+        };
+
+        if dist < params.distance {
+            // Stronger push the closer the boid is (more than factor once inside)
+            let strength = params.factor * (1.0 - dist / params.distance.max(1e-6));
+            delta_v += obstacle.shape.normal(point) * strength;
+        }
+    }
+    delta_v
+}
+
+/// Returns the distance to the first obstacle or map wall hit by a ray, or None if the
+/// ray is clear.
+fn cast_ray(
+    obstacles: &[Obstacle],
+    world_map: Option<&WorldMap>,
+    origin: Vec2,
+    dir: Vec2,
+    length: f32,
+) -> Option<f32> {
+    let wall = world_map.and_then(|map| map.ray_distance(origin, dir, length));
+    obstacles
+        .iter()
+        .filter_map(|obstacle| obstacle.shape.ray_distance(origin, dir, length))
+        .chain(wall)
+        .reduce(f32::min)
+}
+
+/// Calculates the velocity change steering a boid with raycast vision.
+/// The boid casts a fan of rays centered on its heading; if the ray straight ahead
+/// is blocked, it turns towards the clear ray closest to its heading (or the ray
+/// going furthest if none is clear), more strongly the closer the obstacle ahead.
+/// This handles concave shapes and mazes that a radial push gets stuck in.
+///
+/// # Arguments
+///
+/// * 'boid' - The boid looking ahead.
+/// * 'obstacles' - A slice of all the obstacles of the simulation.
+/// * 'world_map' - The world map whose walls block the rays, if any.
+/// * 'raycast' - The RaycastConfig parameters.
+///
+/// # Returns
+///
+/// * 'Vec2' - The steering velocity change.
+pub fn raycast_velocity_change(
+    boid: &Boid,
+    obstacles: &[Obstacle],
+    world_map: Option<&WorldMap>,
+    raycast: &RaycastConfig,
+) -> Vec2 {
+    let speed = boid.vel.length();
+    let heading = if speed > 1e-6 {
+        boid.vel / speed
+    } else {
+        Vec2::X
+    };
+
+    let Some(ahead) = cast_ray(obstacles, world_map, boid.pos, heading, raycast.length) else {
+        return Vec2::ZERO; // Nothing ahead
+    };
+
+    // Try the rays by increasing angle from the heading, alternating sides (an even
+    // number of rays leaves one out, the fan being symmetric)
+    let half_rays = (raycast.rays.max(3) - 1) / 2;
+    let step = raycast.fov.to_radians() / (2 * half_rays) as f32;
+    let mut best = (heading, ahead);
+    'search: for i in 1..=half_rays {
+        for side in [1.0, -1.0] {
+            let dir = Vec2::from_angle(side * step * i as f32).rotate(heading);
+            match cast_ray(obstacles, world_map, boid.pos, dir, raycast.length) {
+                None => {
+                    best = (dir, raycast.length);
+                    break 'search;
+                }
+                Some(dist) if dist > best.1 => best = (dir, dist),
+                Some(_) => {}
+            }
+        }
+    }
+
+    // Turn the velocity towards the chosen direction, keeping the speed
+    let urgency = 1.0 - ahead / raycast.length;
+    (best.0 * speed.max(1e-3) - boid.vel) * raycast.strength * urgency
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{Point, ShapeConfig};
+
+    fn obstacle(x: f32, y: f32, trajectory: TrajectoryConfig) -> Obstacle {
+        Obstacle::new(&ObstacleConfig {
+            shape: ShapeConfig::Circle { x, y, radius: 5.0 },
+            trajectory,
+        })
+    }
+
+    #[test]
+    fn waypoints_are_walked_through_at_constant_speed() {
+        let points = vec![Point { x: 10.0, y: 0.0 }, Point { x: 10.0, y: 10.0 }];
+        let mut obstacle = obstacle(
+            0.0,
+            0.0,
+            TrajectoryConfig::Waypoints {
+                points,
+                speed: 4.0,
+                looped: true,
+            },
+        );
+        obstacle.update(1.0, (100.0, 100.0));
+        assert_eq!(obstacle.shape.center(), Vec2::new(4.0, 0.0));
+        assert_eq!(obstacle.vel, Vec2::new(4.0, 0.0));
+
+        // Turns the corner within a step, then loops back towards the first point
+        obstacle.update(2.0, (100.0, 100.0));
+        assert_eq!(obstacle.shape.center(), Vec2::new(10.0, 2.0));
+        obstacle.update(2.0, (100.0, 100.0));
+        obstacle.update(2.0, (100.0, 100.0));
+        assert_eq!(obstacle.shape.center(), Vec2::new(10.0, 2.0));
+    }
+
+    #[test]
+    fn wrapping_keeps_the_linear_velocity() {
+        let mut obstacle = obstacle(
+            98.0,
+            50.0,
+            TrajectoryConfig::Linear {
+                vx: 5.0,
+                vy: 0.0,
+                wrap: true,
+            },
+        );
+        obstacle.update(1.0, (100.0, 100.0));
+        assert_eq!(obstacle.shape.center(), Vec2::new(3.0, 50.0));
+        assert_eq!(obstacle.vel, Vec2::new(5.0, 0.0)); // Not the -95 px jump
+    }
+
+    #[test]
+    fn circular_orbit_starts_at_the_obstacle_position() {
+        let mut obstacle = obstacle(
+            50.0,
+            60.0,
+            TrajectoryConfig::Circular {
+                center_x: 50.0,
+                center_y: 50.0,
+                angular_speed: std::f32::consts::FRAC_PI_2,
+            },
+        );
+        obstacle.update(1.0, (100.0, 100.0));
+        let pos = obstacle.shape.center();
+        assert!(pos.distance(Vec2::new(40.0, 50.0)) < 1e-4, "{}", pos);
+    }
+
+    #[test]
+    fn push_out_puts_boids_back_on_the_boundary() {
+        let obstacle = obstacle(0.0, 0.0, TrajectoryConfig::Static);
+        let mut boid = Boid::at(Vec2::new(3.0, 0.0), Vec2::new(-1.0, 2.0));
+        obstacle.push_out(&mut boid);
+        assert!(
+            boid.pos.distance(Vec2::new(5.0, 0.0)) < 1e-5,
+            "{}",
+            boid.pos
+        );
+        assert_eq!(boid.vel, Vec2::new(0.0, 2.0)); // Inward part removed
+
+        // Outside boids are left alone
+        let mut boid = Boid::at(Vec2::new(8.0, 0.0), Vec2::new(-1.0, 0.0));
+        obstacle.push_out(&mut boid);
+        assert_eq!(
+            (boid.pos, boid.vel),
+            (Vec2::new(8.0, 0.0), Vec2::new(-1.0, 0.0))
+        );
+    }
+
+    #[test]
+    fn zero_avoidance_distance_gives_a_finite_push() {
+        let obstacles = [obstacle(0.0, 0.0, TrajectoryConfig::Static)];
+        let params = ObstacleAvoidanceConfig {
+            distance: 0.0,
+            ..ObstacleAvoidanceConfig::default()
+        };
+        let inside = Boid::at(Vec2::new(4.0, 0.0), Vec2::ZERO);
+        let dv = avoidance_velocity_change(&inside, &obstacles, None, &params, 1.0);
+        assert!(dv.is_finite() && dv.x > 0.0, "{}", dv);
+        let outside = Boid::at(Vec2::new(20.0, 0.0), Vec2::ZERO);
+        let dv = avoidance_velocity_change(&outside, &obstacles, None, &params, 1.0);
+        assert_eq!(dv, Vec2::ZERO);
+    }
+}
@@ -0,0 +1,333 @@
+// src/orca.rs
+// Optimal Reciprocal Collision Avoidance (ORCA).
+// Each neighbor defines a half-plane of permitted velocities; the new velocity is the
+// one closest to the preferred (flocking) velocity inside all half-planes and the
+// max speed circle, found with the incremental linear programs of the RVO2 library.
+
+use ggez::glam::Vec2;
+
+use crate::boids::Boid;
+
+const EPSILON: f32 = 1e-5;
+
+// --- Line Struct Definition ---
+
+// A directed line bounding a half-plane: permitted velocities lie on its left side
+#[derive(Debug, Clone, Copy)]
+struct Line {
+    point: Vec2,
+    direction: Vec2,
+}
+
+/// Computes the collision-free velocity closest to the preferred velocity.
+///
+/// # Arguments
+///
+/// * 'boid' - The boid whose velocity is computed.
+/// * 'preferred' - The velocity the flocking rules would choose.
+/// * 'neighbors' - A slice of references to the neighbors to avoid.
+/// * 'radius' - Body radius of every boid.
+/// * 'time_horizon' - Collisions within this many steps are avoided.
+/// * 'time_step' - Duration of one step (the config dt).
+/// * 'max_speed' - Maximum speed of the boid.
+///
+/// # Returns
+///
+/// * 'Vec2' - The new velocity.
+pub fn collision_free_velocity(
+    boid: &Boid,
+    preferred: Vec2,
+    neighbors: &[&Boid],
+    radius: f32,
+    time_horizon: f32,
+    time_step: f32,
+    max_speed: f32,
+) -> Vec2 {
+    let inv_time_horizon = 1.0 / time_horizon.max(EPSILON);
+    let inv_time_step = 1.0 / time_step.max(EPSILON);
+    let combined_radius = 2.0 * radius;
+    let combined_radius_sq = combined_radius * combined_radius;
+
+    // --- Build one ORCA half-plane per neighbor ---
+    let lines: Vec<Line> = neighbors
+        .iter()
+        .map(|other| {
+            let relative_position = other.pos - boid.pos;
+            let relative_velocity = boid.vel - other.vel;
+            let dist_sq = relative_position.length_squared();
+
+            let (direction, u) = if dist_sq > combined_radius_sq {
+                // No collision: vector from cutoff center to relative velocity
+                let w = relative_velocity - relative_position * inv_time_horizon;
+                let w_length_sq = w.length_squared();
+                let dot = w.dot(relative_position);
+
+                if dot < 0.0 && dot * dot > combined_radius_sq * w_length_sq {
+                    // Project on the cutoff circle
+                    let w_length = w_length_sq.sqrt();
+                    let unit_w = w / w_length;
+                    let direction = Vec2::new(unit_w.y, -unit_w.x);
+                    (
+                        direction,
+                        unit_w * (combined_radius * inv_time_horizon - w_length),
+                    )
+                } else {
+                    // Project on the legs of the velocity obstacle
+                    let leg = (dist_sq - combined_radius_sq).sqrt();
+                    let p = relative_position;
+                    let direction = if p.perp_dot(w) > 0.0 {
+                        // Left leg
+                        Vec2::new(
+                            p.x * leg - p.y * combined_radius,
+                            p.x * combined_radius + p.y * leg,
+                        ) / dist_sq
+                    } else {
+                        // Right leg
+                        -Vec2::new(
+                            p.x * leg + p.y * combined_radius,
+                            -p.x * combined_radius + p.y * leg,
+                        ) / dist_sq
+                    };
+                    let dot = relative_velocity.dot(direction);
+                    (direction, direction * dot - relative_velocity)
+                }
+            } else {
+                // Already colliding: project on the cutoff circle of one time step
+                let w = relative_velocity - relative_position * inv_time_step;
+                let w_length = w.length().max(EPSILON);
+                let unit_w = w / w_length;
+                let direction = Vec2::new(unit_w.y, -unit_w.x);
+                (
+                    direction,
+                    unit_w * (combined_radius * inv_time_step - w_length),
+                )
+            };
+
+            // Each boid takes half of the responsibility of avoiding the collision
+            Line {
+                point: boid.vel + u * 0.5,
+                direction,
+            }
+        })
+        .collect();
+
+    // --- Solve the linear program ---
+    let mut result = Vec2::ZERO;
+    let line_fail = linear_program2(&lines, max_speed, preferred, false, &mut result);
+    if line_fail < lines.len() {
+        // Infeasible: minimize the maximum violation of the remaining constraints
+        linear_program3(&lines, line_fail, max_speed, &mut result);
+    }
+    result
+}
+
+/// Solves a one-dimensional linear program on the given line.
+/// Returns false if the program is infeasible.
+fn linear_program1(
+    lines: &[Line],
+    line_no: usize,
+    radius: f32,
+    opt_velocity: Vec2,
+    direction_opt: bool,
+    result: &mut Vec2,
+) -> bool {
+    let line = lines[line_no];
+    let dot = line.point.dot(line.direction);
+    let discriminant = dot * dot + radius * radius - line.point.length_squared();
+    if discriminant < 0.0 {
+        // The max speed circle fully invalidates this line
+        return false;
+    }
+
+    let sqrt_discriminant = discriminant.sqrt();
+    let mut t_left = -dot - sqrt_discriminant;
+    let mut t_right = -dot + sqrt_discriminant;
+
+    for other in &lines[..line_no] {
+        let denominator = line.direction.perp_dot(other.direction);
+        let numerator = other.direction.perp_dot(line.point - other.point);
+
+        if denominator.abs() <= EPSILON {
+            // Lines are (almost) parallel
+            if numerator < 0.0 {
+                return false;
+            }
+            continue;
+        }
+
+        let t = numerator / denominator;
+        if denominator >= 0.0 {
+            t_right = t_right.min(t); // Line bounds the line on the right
+        } else {
+            t_left = t_left.max(t); // Line bounds the line on the left
+        }
+        if t_left > t_right {
+            return false;
+        }
+    }
+
+    let t = if direction_opt {
+        // Optimize direction
+        if opt_velocity.dot(line.direction) > 0.0 {
+            t_right
+        } else {
+            t_left
+        }
+    } else {
+        // Optimize closest point
+        let t = line.direction.dot(opt_velocity - line.point);
+        if t < t_left {
+            t_left
+        } else if t > t_right {
+            t_right
+        } else {
+            t
+        }
+    };
+    *result = line.point + line.direction * t;
+    true
+}
+
+/// Solves a two-dimensional linear program subject to the lines and the circular constraint.
+/// Returns the number of the line it fails on, or the number of lines if successful.
+fn linear_program2(
+    lines: &[Line],
+    radius: f32,
+    opt_velocity: Vec2,
+    direction_opt: bool,
+    result: &mut Vec2,
+) -> usize {
+    *result = if direction_opt {
+        // The optimization velocity is a unit vector here
+        opt_velocity * radius
+    } else if opt_velocity.length_squared() > radius * radius {
+        // Optimize the closest point, outside the circle
+        opt_velocity.normalize() * radius
+    } else {
+        // Optimize the closest point, inside the circle
+        opt_velocity
+    };
+
+    for (i, line) in lines.iter().enumerate() {
+        if line.direction.perp_dot(line.point - *result) > 0.0 {
+            // The result does not satisfy this constraint, compute a new optimal result
+            let previous = *result;
+            if !linear_program1(lines, i, radius, opt_velocity, direction_opt, result) {
+                *result = previous;
+                return i;
+            }
+        }
+    }
+    lines.len()
+}
+
+/// Solves the three-dimensional linear program used when the two-dimensional one is
+/// infeasible: finds the velocity minimizing the maximum constraint violation.
+fn linear_program3(lines: &[Line], begin_line: usize, radius: f32, result: &mut Vec2) {
+    let mut distance = 0.0;
+
+    for i in begin_line..lines.len() {
+        let line = lines[i];
+        if line.direction.perp_dot(line.point - *result) <= distance {
+            continue; // The result already satisfies this constraint within the distance
+        }
+
+        // Project the previous constraints on this line
+        let mut projected = Vec::with_capacity(i);
+        for other in &lines[..i] {
+            let determinant = line.direction.perp_dot(other.direction);
+            let point = if determinant.abs() <= EPSILON {
+                if line.direction.dot(other.direction) > 0.0 {
+                    continue; // Same direction
+                }
+                // Opposite direction
+                (line.point + other.point) * 0.5
+            } else {
+                line.point
+                    + line.direction
+                        * (other.direction.perp_dot(line.point - other.point) / determinant)
+            };
+            projected.push(Line {
+                point,
+                direction: (other.direction - line.direction).normalize_or_zero(),
+            });
+        }
+
+        let previous = *result;
+        let opt_direction = Vec2::new(-line.direction.y, line.direction.x);
+        if linear_program2(&projected, radius, opt_direction, true, result) < projected.len() {
+            // Should not happen in principle (floating point error), keep the previous result
+            *result = previous;
+        }
+        distance = line.direction.perp_dot(line.point - *result);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keeps_preferred_velocity_without_neighbors() {
+        let a = Boid::at(Vec2::ZERO, Vec2::new(1.0, 0.0));
+        let preferred = Vec2::new(0.5, 1.0);
+        let vel = collision_free_velocity(&a, preferred, &[], 2.0, 10.0, 1.0, 3.0);
+        assert!((vel - preferred).length() < 1e-4);
+    }
+
+    #[test]
+    fn head_on_agents_do_not_overlap() {
+        let (radius, time_horizon, time_step, max_speed) = (2.0, 10.0, 1.0, 2.0);
+        let mut a = Boid::at(Vec2::new(0.0, 0.0), Vec2::new(2.0, 0.0));
+        let mut b = Boid::at(Vec2::new(40.0, 0.0), Vec2::new(-2.0, 0.0));
+        for _ in 0..60 {
+            // Both agents keep heading for each other's side
+            let vel_a = collision_free_velocity(
+                &a,
+                Vec2::new(2.0, 0.0),
+                &[&b],
+                radius,
+                time_horizon,
+                time_step,
+                max_speed,
+            );
+            let vel_b = collision_free_velocity(
+                &b,
+                Vec2::new(-2.0, 0.0),
+                &[&a],
+                radius,
+                time_horizon,
+                time_step,
+                max_speed,
+            );
+            a.vel = vel_a;
+            b.vel = vel_b;
+            a.pos += a.vel * time_step;
+            b.pos += b.vel * time_step;
+            assert!(a.pos.distance(b.pos) >= 2.0 * radius - 1e-3);
+            assert!(a.vel.length() <= max_speed + 1e-4 && b.vel.length() <= max_speed + 1e-4);
+        }
+        assert!(
+            a.pos.x > b.pos.x,
+            "the agents should have passed each other"
+        );
+    }
+
+    #[test]
+    fn infeasible_constraints_stay_within_max_speed() {
+        // Surrounded by overlapping neighbors rushing in: no velocity avoids them all
+        let max_speed = 1.5;
+        let a = Boid::at(Vec2::ZERO, Vec2::new(1.0, 0.0));
+        let neighbors: Vec<Boid> = (0..8)
+            .map(|k| {
+                let dir = Vec2::from_angle(k as f32 * std::f32::consts::PI / 4.0);
+                Boid::at(dir * 3.0, -dir * 2.0)
+            })
+            .collect();
+        let refs: Vec<&Boid> = neighbors.iter().collect();
+        let vel =
+            collision_free_velocity(&a, Vec2::new(1.5, 0.0), &refs, 2.0, 10.0, 1.0, max_speed);
+        assert!(vel.is_finite());
+        assert!(vel.length() <= max_speed + 1e-4);
+    }
+}
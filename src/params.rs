@@ -0,0 +1,117 @@
+// src/params.rs
+// Local overrides of the boid parameters, applied where the environment
+// (map regions) changes how boids behave, and typed access to the numeric
+// parameters changed during a run (timeline events and schedules).
+
+use crate::config::{BoidsConfig, Param, ParamOverrides};
+use crate::genetics::Genes;
+
+// --- ParamOverrides Implementation ---
+
+impl ParamOverrides {
+    /// Applies the overrides to a config and to a boid's genes.
+    /// Each overridden value is blended towards its override by the given weight.
+    ///
+    /// # Arguments
+    ///
+    /// * 'config' - The BoidsConfig to modify (speed limits, turning).
+    /// * 'genes' - The Genes to modify (rule factors and ranges).
+    /// * 'weight' - Blend weight, 0.0 keeps the values, 1.0 replaces them.
+    pub fn apply(&self, config: &mut BoidsConfig, genes: &mut Genes, weight: f32) {
+        let blend = |value: &mut f32, target: Option<f32>| {
+            if let Some(target) = target {
+                *value += (target - *value) * weight;
+            }
+        };
+        blend(&mut genes.protected_range, self.protected_range);
+        blend(&mut genes.visible_range, self.visible_range);
+        blend(&mut genes.avoidfactor, self.avoidfactor);
+        blend(&mut genes.matchingfactor, self.matchingfactor);
+        blend(&mut genes.centeringfactor, self.centeringfactor);
+        blend(&mut config.turnfactor, self.turnfactor);
+        blend(&mut config.maxspeed, self.maxspeed);
+        blend(&mut config.minspeed, self.minspeed);
+    }
+}
+
+// --- Param Implementation ---
+
+impl Param {
+    /// Returns whether the parameter exists in a config, i.e. its section (or mode) is
+    /// enabled.
+    ///
+    /// # Arguments
+    ///
+    /// * 'config' - The BoidsConfig to look in.
+    pub fn is_enabled(&self, config: &BoidsConfig) -> bool {
+        let mut config = *config;
+        match self {
+            Param::F32(field) => field(&mut config).is_some(),
+            Param::U32(field) => field(&mut config).is_some(),
+            Param::U64(field) => field(&mut config).is_some(),
+            Param::Usize(field) => field(&mut config).is_some(),
+        }
+    }
+
+    /// Checks that values between two bounds fit the parameter's type.
+    ///
+    /// # Arguments
+    ///
+    /// * 'min' - The smallest value.
+    /// * 'max' - The largest value.
+    ///
+    /// # Returns
+    ///
+    /// * Result<(), String> - An error if a value is not finite, or rounds to a
+    ///   negative or too large integer for an integer parameter.
+    pub fn check_range(&self, min: f32, max: f32) -> Result<(), String> {
+        if !min.is_finite() || !max.is_finite() {
+            return Err(format!("values must be finite, got {} to {}", min, max));
+        }
+        let (limit, kind) = match self {
+            Param::F32(_) => return Ok(()),
+            Param::U32(_) => (u32::MAX as f32, "u32"),
+            Param::U64(_) => (u64::MAX as f32, "u64"),
+            Param::Usize(_) => (usize::MAX as f32, "usize"),
+        };
+        if min.round() < 0.0 || max.round() > limit {
+            return Err(format!(
+                "values from {} to {} do not fit an integer parameter ({})",
+                min, max, kind
+            ));
+        }
+        Ok(())
+    }
+
+    /// Sets the parameter in a config, integer parameters taking the nearest integer.
+    /// Does nothing if its section is not enabled (checked when it was resolved).
+    ///
+    /// # Arguments
+    ///
+    /// * 'config' - The BoidsConfig to modify.
+    /// * 'value' - The new value, within the range accepted by check_range.
+    pub fn set(&self, config: &mut BoidsConfig, value: f32) {
+        match self {
+            Param::F32(field) => {
+                if let Some(param) = field(config) {
+                    *param = value;
+                }
+            }
+            Param::U32(field) => {
+                if let Some(param) = field(config) {
+                    *param = value.round() as u32;
+                }
+            }
+            Param::U64(field) => {
+                if let Some(param) = field(config) {
+                    *param = value.round() as u64;
+                }
+            }
+            Param::Usize(field) => {
+                if let Some(param) = field(config) {
+                    *param = value.round() as usize;
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,93 @@
+// src/perception.rs
+// Imperfect perception: boids see their neighbors where they were some steps ago
+// (sensory or radio latency), with Gaussian noise on positions and headings.
+
+use std::collections::{HashMap, VecDeque};
+
+use ggez::glam::Vec2;
+use rand::Rng;
+use rand_distr::{Distribution, Normal};
+use uuid::Uuid;
+
+use crate::boids::Boid;
+use crate::config::PerceptionConfig;
+
+// --- PastStates Struct Definition ---
+
+// Ring buffer of the positions and velocities of the boids over the last steps
+#[derive(Debug, Clone, Default)]
+pub struct PastStates {
+    snapshots: VecDeque<HashMap<Uuid, (Vec2, Vec2)>>, // oldest first, (position, velocity) by id
+}
+
+// --- PastStates Implementation ---
+
+impl PastStates {
+    /// Records the current state of the boids, keeping the snapshots of the last
+    /// delay steps before it.
+    ///
+    /// # Arguments
+    ///
+    /// * 'boids' - A slice of all the boids of the simulation.
+    /// * 'delay' - The perception delay, in steps.
+    pub fn record(&mut self, boids: &[Boid], delay: usize) {
+        let mut snapshot = if self.snapshots.len() > delay {
+            // Reuse the allocation of the snapshot falling out of the buffer
+            let mut oldest = self.snapshots.pop_front().unwrap_or_default();
+            oldest.clear();
+            oldest
+        } else {
+            HashMap::with_capacity(boids.len())
+        };
+        snapshot.extend(boids.iter().map(|boid| (boid.id, (boid.pos, boid.vel))));
+        self.snapshots.push_back(snapshot);
+        while self.snapshots.len() > delay + 1 {
+            self.snapshots.pop_front();
+        }
+    }
+
+    /// Returns the oldest recorded state of a boid (delay steps ago once the buffer is
+    /// full), or None if the boid was born since.
+    pub fn delayed(&self, id: &Uuid) -> Option<(Vec2, Vec2)> {
+        self.snapshots.front()?.get(id).copied()
+    }
+}
+
+/// Returns a neighbor as a boid perceives it: at its delayed position and velocity,
+/// with noise added to the position and the heading.
+///
+/// # Arguments
+///
+/// * 'neighbor' - The neighbor.
+/// * 'past' - The past states of the boids.
+/// * 'config' - The PerceptionConfig parameters.
+/// * 'rng' - A mutable reference to a random number generator.
+///
+/// # Returns
+///
+/// * 'Boid' - A copy of the neighbor with its perceived position and velocity.
+pub fn perceive(
+    neighbor: &Boid,
+    past: &PastStates,
+    config: &PerceptionConfig,
+    rng: &mut impl Rng,
+) -> Boid {
+    let mut perceived = neighbor.clone();
+    if config.delay > 0
+        && let Some((pos, vel)) = past.delayed(&neighbor.id)
+    {
+        perceived.pos = pos;
+        perceived.vel = vel;
+    }
+    if config.position_noise > 0.0
+        && let Ok(normal) = Normal::new(0.0, config.position_noise)
+    {
+        perceived.pos += Vec2::new(normal.sample(rng), normal.sample(rng));
+    }
+    if config.heading_noise > 0.0
+        && let Ok(normal) = Normal::new(0.0, config.heading_noise.to_radians())
+    {
+        perceived.vel = Vec2::from_angle(normal.sample(rng)).rotate(perceived.vel);
+    }
+    perceived
+}
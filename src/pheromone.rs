@@ -0,0 +1,209 @@
+// src/pheromone.rs
+// Pheromone field: a scalar grid over the world that boids deposit into, which
+// diffuses and evaporates every step, and whose gradient boids can follow
+// (stigmergic communication, ant-like trails).
+
+use ggez::glam::Vec2;
+use rayon::prelude::*;
+
+use crate::boids::Boid;
+use crate::config::PheromoneConfig;
+
+// --- PheromoneGrid Struct Definition ---
+
+#[derive(Debug, Clone)]
+pub struct PheromoneGrid {
+    cols: usize,
+    rows: usize,
+    cell_size: f32,
+    values: Vec<f32>, // concentration per cell, row-major
+    buffer: Vec<f32>, // scratch buffer for the diffusion
+}
+
+// --- PheromoneGrid Implementation ---
+
+impl PheromoneGrid {
+    /// Creates an empty grid covering the world.
+    ///
+    /// # Arguments
+    ///
+    /// * 'world_dims' - A tuple (width, height) of the simulation area.
+    /// * 'cell_size' - Side of a grid cell.
+    ///
+    /// # Returns
+    ///
+    /// * 'Self' - A new PheromoneGrid instance.
+    pub fn new(world_dims: (f32, f32), cell_size: f32) -> Self {
+        let cell_size = cell_size.max(1.0);
+        let cols = (world_dims.0 / cell_size).ceil().max(1.0) as usize;
+        let rows = (world_dims.1 / cell_size).ceil().max(1.0) as usize;
+        PheromoneGrid {
+            cols,
+            rows,
+            cell_size,
+            values: vec![0.0; cols * rows],
+            buffer: vec![0.0; cols * rows],
+        }
+    }
+
+    /// Returns the (column, row) of the cell containing a point, clamped to the grid.
+    fn cell_of(&self, p: Vec2) -> (usize, usize) {
+        let c = (p.x / self.cell_size)
+            .floor()
+            .clamp(0.0, (self.cols - 1) as f32);
+        let r = (p.y / self.cell_size)
+            .floor()
+            .clamp(0.0, (self.rows - 1) as f32);
+        (c as usize, r as usize)
+    }
+
+    /// Adds pheromone in the cell of every boid.
+    ///
+    /// # Arguments
+    ///
+    /// * 'boids' - A slice of all the boids of the simulation.
+    /// * 'amount' - Pheromone deposited by each boid.
+    pub fn deposit(&mut self, boids: &[Boid], amount: f32) {
+        for boid in boids {
+            let (c, r) = self.cell_of(boid.pos);
+            self.values[r * self.cols + c] += amount;
+        }
+    }
+
+    /// Diffuses and evaporates the pheromone for one step.
+    /// Each cell keeps (1 - diffusion) of its value and takes diffusion times the
+    /// average of its 4 neighbors (the edges reflect), then loses a fraction
+    /// evaporation of the result.
+    ///
+    /// # Arguments
+    ///
+    /// * 'diffusion' - Fraction of the value exchanged with the neighbors.
+    /// * 'evaporation' - Fraction of the value lost.
+    pub fn diffuse(&mut self, diffusion: f32, evaporation: f32) {
+        let (cols, rows) = (self.cols, self.rows);
+        let values = &self.values;
+        let keep = 1.0 - evaporation;
+        self.buffer
+            .par_chunks_mut(cols)
+            .enumerate()
+            .for_each(|(r, row)| {
+                let up = r.saturating_sub(1);
+                let down = (r + 1).min(rows - 1);
+                for (c, value) in row.iter_mut().enumerate() {
+                    let left = c.saturating_sub(1);
+                    let right = (c + 1).min(cols - 1);
+                    let here = values[r * cols + c];
+                    let neighbors = values[r * cols + left]
+                        + values[r * cols + right]
+                        + values[up * cols + c]
+                        + values[down * cols + c];
+                    *value = (here + diffusion * (neighbors * 0.25 - here)) * keep;
+                }
+            });
+        std::mem::swap(&mut self.values, &mut self.buffer);
+    }
+
+    /// Returns the concentration at a point, interpolated between cell centers.
+    pub fn sample(&self, p: Vec2) -> f32 {
+        let x = (p.x / self.cell_size - 0.5).clamp(0.0, (self.cols - 1) as f32);
+        let y = (p.y / self.cell_size - 0.5).clamp(0.0, (self.rows - 1) as f32);
+        let (c0, r0) = (x.floor() as usize, y.floor() as usize);
+        let (c1, r1) = ((c0 + 1).min(self.cols - 1), (r0 + 1).min(self.rows - 1));
+        let (fx, fy) = (x - c0 as f32, y - r0 as f32);
+        let at = |c: usize, r: usize| self.values[r * self.cols + c];
+        let top = at(c0, r0) * (1.0 - fx) + at(c1, r0) * fx;
+        let bottom = at(c0, r1) * (1.0 - fx) + at(c1, r1) * fx;
+        top * (1.0 - fy) + bottom * fy
+    }
+
+    /// Returns the gradient of the concentration at a point (central differences).
+    pub fn gradient(&self, p: Vec2) -> Vec2 {
+        let h = self.cell_size;
+        Vec2::new(
+            self.sample(p + Vec2::new(h, 0.0)) - self.sample(p - Vec2::new(h, 0.0)),
+            self.sample(p + Vec2::new(0.0, h)) - self.sample(p - Vec2::new(0.0, h)),
+        ) / (2.0 * h)
+    }
+
+    /// Calculates the velocity change steering a boid up the pheromone gradient.
+    ///
+    /// # Arguments
+    ///
+    /// * 'boid' - The boid following the gradient.
+    /// * 'config' - The PheromoneConfig parameters.
+    ///
+    /// # Returns
+    ///
+    /// * 'Vec2' - The gradient following velocity change.
+    pub fn velocity_change(&self, boid: &Boid, config: &PheromoneConfig) -> Vec2 {
+        let gradient = self.gradient(boid.pos);
+        if gradient.length() < config.sensitivity {
+            return Vec2::ZERO; // Too faint to be sensed
+        }
+        gradient.normalize_or_zero() * config.factor // Zero on a flat field
+    }
+
+    /// Saves the grid as a grayscale PNG image (one pixel per cell), white being
+    /// the highest concentration.
+    ///
+    /// # Arguments
+    ///
+    /// * 'path' - The path of the image file.
+    pub fn save_png(&self, path: &str) -> Result<(), image::ImageError> {
+        let max = self.values.iter().copied().fold(0.0, f32::max);
+        let scale = if max > 0.0 { 255.0 / max } else { 0.0 };
+        let pixels: Vec<u8> = self
+            .values
+            .iter()
+            .map(|value| (value * scale).round().clamp(0.0, 255.0) as u8)
+            .collect();
+        image::save_buffer(
+            path,
+            &pixels,
+            self.cols as u32,
+            self.rows as u32,
+            image::ColorType::L8,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config() -> PheromoneConfig {
+        PheromoneConfig {
+            cell_size: 10.0,
+            deposit: 1.0,
+            diffusion: 0.1,
+            evaporation: 0.01,
+            factor: 0.5,
+            sensitivity: 0.0,
+        }
+    }
+
+    #[test]
+    fn empty_grid_gives_no_steering() {
+        let grid = PheromoneGrid::new((100.0, 100.0), 10.0);
+        let dv = grid.velocity_change(&Boid::at(Vec2::new(45.0, 55.0), Vec2::ZERO), &config());
+        assert_eq!(dv, Vec2::ZERO);
+    }
+
+    #[test]
+    fn uniform_grid_gives_no_steering() {
+        let mut grid = PheromoneGrid::new((100.0, 100.0), 10.0);
+        grid.values.iter_mut().for_each(|value| *value = 3.0);
+        let dv = grid.velocity_change(&Boid::at(Vec2::new(45.0, 55.0), Vec2::ZERO), &config());
+        assert_eq!(dv, Vec2::ZERO);
+    }
+
+    #[test]
+    fn boids_steer_up_the_gradient() {
+        let mut grid = PheromoneGrid::new((100.0, 100.0), 10.0);
+        grid.deposit(&[Boid::at(Vec2::new(75.0, 55.0), Vec2::ZERO)], 10.0);
+        grid.diffuse(0.5, 0.0);
+        let dv = grid.velocity_change(&Boid::at(Vec2::new(55.0, 55.0), Vec2::ZERO), &config());
+        assert!(dv.x > 0.0 && dv.is_finite());
+        assert!((dv.length() - config().factor).abs() < 1e-5);
+    }
+}
@@ -0,0 +1,143 @@
+// src/projection.rs
+// Projection-based interaction (Pearce et al. 2014): every boid sees the others as
+// dark discs against a light background, steers towards the edges of this visual
+// projection and aligns with the boids not hidden behind closer ones.
+
+use std::f32::consts::{FRAC_PI_2, TAU};
+
+use ggez::glam::Vec2;
+
+use crate::boids::Boid;
+
+// --- Projection Struct Definition ---
+
+// What a boid sees of the others
+#[derive(Debug, Clone)]
+pub struct Projection {
+    pub boundary: Vec2,     // mean unit vector towards the edges of the projection
+    pub opacity: f32,       // fraction of the visual field covered by other boids
+    pub visible: Vec<bool>, // whether each of the others is at least partly visible
+}
+
+/// Computes the visual projection of other boids seen from a position. The others
+/// are seen as discs, closer ones hiding further ones.
+///
+/// # Arguments
+///
+/// * 'pos' - The position of the observer.
+/// * 'others' - The other boids.
+/// * 'body_radius' - The radius of the disc every boid appears as.
+///
+/// # Returns
+///
+/// * 'Projection' - The edges, opacity and visible boids of the projection.
+pub fn project(pos: Vec2, others: &[&Boid], body_radius: f32) -> Projection {
+    // Angular interval of every other boid, closest first
+    let mut order: Vec<(f32, usize)> = others
+        .iter()
+        .enumerate()
+        .map(|(i, other)| (pos.distance(other.pos), i))
+        .collect();
+    order.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+    let mut covered: Vec<(f32, f32)> = Vec::new(); // disjoint intervals of [0, TAU], sorted
+    let mut visible = vec![false; others.len()];
+    for (distance, i) in order {
+        let half_width = if distance <= body_radius {
+            FRAC_PI_2 // Touching: hides half of the visual field
+        } else {
+            (body_radius / distance).asin()
+        };
+        let to_other = others[i].pos - pos;
+        let start = (to_other.y.atan2(to_other.x) - half_width).rem_euclid(TAU);
+        let end = start + 2.0 * half_width;
+        // Split the intervals crossing the origin of the angles
+        let pieces = if end > TAU {
+            vec![(start, TAU), (0.0, end - TAU)]
+        } else {
+            vec![(start, end)]
+        };
+        visible[i] = pieces
+            .iter()
+            .any(|&(a, b)| !covered.iter().any(|&(s, e)| s <= a && b <= e));
+        for (a, b) in pieces {
+            insert_interval(&mut covered, a, b);
+        }
+    }
+
+    // Edges of the dark intervals, except where an interval continues across the origin
+    const EPSILON: f32 = 1e-6;
+    let wraps = covered.len() > 1
+        && covered.first().is_some_and(|&(s, _)| s <= EPSILON)
+        && covered.last().is_some_and(|&(_, e)| e >= TAU - EPSILON);
+    let mut edges = Vec::with_capacity(covered.len() * 2);
+    for (k, &(s, e)) in covered.iter().enumerate() {
+        if !(wraps && k == 0 && s <= EPSILON) {
+            edges.push(s);
+        }
+        if !(wraps && k == covered.len() - 1 && e >= TAU - EPSILON) {
+            edges.push(e);
+        }
+    }
+    let opacity = covered.iter().map(|(s, e)| e - s).sum::<f32>() / TAU;
+    let boundary = if edges.is_empty() || opacity >= 1.0 - EPSILON {
+        Vec2::ZERO // Nothing or everything is dark: no edges
+    } else {
+        edges
+            .iter()
+            .map(|&angle| Vec2::from_angle(angle))
+            .sum::<Vec2>()
+            / edges.len() as f32
+    };
+
+    Projection {
+        boundary,
+        opacity: opacity.min(1.0),
+        visible,
+    }
+}
+
+/// Adds an interval to a sorted list of disjoint intervals, merging the overlaps.
+fn insert_interval(intervals: &mut Vec<(f32, f32)>, start: f32, end: f32) {
+    let (mut start, mut end) = (start, end);
+    intervals.retain(|&(s, e)| {
+        if s <= end && start <= e {
+            start = start.min(s);
+            end = end.max(e);
+            false // Merged into the new interval
+        } else {
+            true
+        }
+    });
+    let index = intervals.partition_point(|&(s, _)| s < start);
+    intervals.insert(index, (start, end));
+}
+
+/// Calculates the projection steering of a boid: towards the edges of its projection,
+/// and matching the average velocity of the visible boids.
+///
+/// # Arguments
+///
+/// * 'boid' - The boid.
+/// * 'projection' - What the boid sees.
+/// * 'visible' - The visible boids.
+/// * 'projection_factor' - Strength of the steering towards the edges.
+/// * 'alignment_factor' - Strength of the velocity matching.
+///
+/// # Returns
+///
+/// * 'Vec2' - The projection velocity change.
+pub fn velocity_change(
+    boid: &Boid,
+    projection: &Projection,
+    visible: &[&Boid],
+    projection_factor: f32,
+    alignment_factor: f32,
+) -> Vec2 {
+    let mut delta_v = projection.boundary * projection_factor;
+    if !visible.is_empty() {
+        let avg_vel = visible.iter().map(|other| other.vel).sum::<Vec2>() / visible.len() as f32;
+        delta_v += (avg_vel - boid.vel) * alignment_factor;
+    }
+    delta_v
+}
@@ -0,0 +1,164 @@
+// src/roost.rs
+// Roosting: over a day cycle each flock is pulled ever more strongly towards its
+// roost, lands there at dusk and takes off again at dawn (evening murmurations).
+
+use ggez::glam::Vec2;
+
+use crate::boids::Boid;
+use crate::config::{RoostConfig, RoostingConfig};
+
+// --- RoostState Enum Definition ---
+
+#[derive(Debug, Clone, Copy, Default)]
+pub enum RoostState {
+    // No roost assigned (daytime)
+    #[default]
+    Free,
+    // Flying back to its roost
+    Homing {
+        roost: usize,
+    },
+    // Settled on its roost until dawn
+    Landed {
+        roost: usize,
+    },
+}
+
+// --- RoostState Implementation ---
+
+impl RoostState {
+    /// Returns the roost assigned to the boid, if any.
+    pub fn roost(&self) -> Option<usize> {
+        match *self {
+            RoostState::Free => None,
+            RoostState::Homing { roost } | RoostState::Landed { roost } => Some(roost),
+        }
+    }
+
+    /// Returns true if the boid has landed on its roost.
+    pub fn is_landed(&self) -> bool {
+        matches!(self, RoostState::Landed { .. })
+    }
+}
+
+// --- Roost Struct Definition ---
+
+#[derive(Debug, Clone, Copy)]
+pub struct Roost {
+    pub pos: Vec2,
+    pub radius: f32,
+}
+
+// --- Roost Implementation ---
+
+impl Roost {
+    /// Creates a new Roost from its configuration.
+    ///
+    /// # Arguments
+    ///
+    /// * 'config' - The RoostConfig read from boids.yaml.
+    ///
+    /// # Returns
+    ///
+    /// * 'Self' - A new Roost instance.
+    pub fn new(config: &RoostConfig) -> Self {
+        Roost {
+            pos: Vec2::new(config.x, config.y),
+            radius: config.radius,
+        }
+    }
+
+    /// Returns true if a point is within the landing radius.
+    pub fn contains(&self, p: Vec2) -> bool {
+        self.pos.distance_squared(p) <= self.radius * self.radius
+    }
+
+    /// Returns the speed limit of a boid approaching the roost at night: maxspeed at
+    /// the approach distance from the edge, down to the landing speed at the edge.
+    ///
+    /// # Arguments
+    ///
+    /// * 'p' - The position of the boid.
+    /// * 'maxspeed' - The speed limit away from the roost.
+    /// * 'params' - The RoostingConfig parameters.
+    pub fn speed_limit(&self, p: Vec2, maxspeed: f32, params: &RoostingConfig) -> f32 {
+        let from_edge = (self.pos.distance(p) - self.radius).max(0.0);
+        let t = (from_edge / params.approach_distance.max(1e-6)).min(1.0);
+        params.landing_speed + (maxspeed - params.landing_speed).max(0.0) * t
+    }
+
+    /// Calculates the velocity change pulling a boid towards the roost.
+    ///
+    /// # Arguments
+    ///
+    /// * 'boid' - The homing boid.
+    /// * 'strength' - The current homing strength.
+    ///
+    /// # Returns
+    ///
+    /// * 'Vec2' - The homing velocity change.
+    pub fn homing_velocity_change(&self, boid: &Boid, strength: f32) -> Vec2 {
+        (self.pos - boid.pos).normalize_or_zero() * strength
+    }
+}
+
+/// Returns the phase of the day at a step, in [0, 1), dawn being at 0.
+pub fn day_phase(step: u64, params: &RoostingConfig) -> f32 {
+    let day_length = params.day_length.max(1);
+    (step % day_length) as f32 / day_length as f32
+}
+
+/// Returns the strength of the pull towards the roosts at a phase of the day:
+/// zero until homing_start, growing smoothly to homing_factor at dusk, then full
+/// for the rest of the night.
+pub fn homing_strength(phase: f32, params: &RoostingConfig) -> f32 {
+    if phase < params.homing_start {
+        return 0.0;
+    }
+    let span = (params.dusk - params.homing_start).max(1e-6);
+    let t = ((phase - params.homing_start) / span).clamp(0.0, 1.0);
+    params.homing_factor * t * t * (3.0 - 2.0 * t)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn params() -> RoostingConfig {
+        RoostingConfig {
+            day_length: 100,
+            homing_start: 0.5,
+            dusk: 0.7,
+            homing_factor: 2.0,
+            approach_distance: 50.0,
+            landing_speed: 0.5,
+            settle_factor: 0.1,
+        }
+    }
+
+    #[test]
+    fn day_phase_wraps_every_day() {
+        let params = params();
+        assert_eq!(day_phase(0, &params), 0.0);
+        assert_eq!(day_phase(25, &params), 0.25);
+        assert_eq!(day_phase(100, &params), 0.0);
+        assert_eq!(day_phase(375, &params), 0.75);
+    }
+
+    #[test]
+    fn homing_grows_smoothly_from_homing_start_to_dusk() {
+        let params = params();
+        assert_eq!(homing_strength(0.2, &params), 0.0);
+        assert_eq!(homing_strength(0.5, &params), 0.0);
+        assert!((homing_strength(0.6, &params) - 1.0).abs() < 1e-5); // Halfway
+        assert_eq!(homing_strength(0.7, &params), 2.0);
+        assert_eq!(homing_strength(0.95, &params), 2.0); // Full for the night
+
+        // Zero dusk span: full strength right after homing_start
+        let abrupt = RoostingConfig {
+            dusk: 0.5,
+            ..params
+        };
+        assert_eq!(homing_strength(0.51, &abrupt), 2.0);
+    }
+}
@@ -0,0 +1,205 @@
+// src/scenario.rs
+// Scenario files: exact initial conditions (position, velocity and optionally the
+// family group and behavioural state of every boid) read from CSV or JSON, used
+// instead of random spawning for reproducible runs.
+
+use std::path::Path;
+
+use serde::Deserialize;
+
+use crate::boids::Boid;
+use crate::config::BehaviorState;
+use crate::timeline::TimelineEvent;
+
+// --- ScenarioBoid Struct Definition ---
+
+// Initial state of one boid
+#[derive(Deserialize, Debug, Clone, Copy)]
+pub struct ScenarioBoid {
+    pub x: f32,
+    pub y: f32,
+    pub vx: f32,
+    pub vy: f32,
+    #[serde(default)]
+    pub group: Option<usize>, // family group (species), used when groups are enabled
+    #[serde(default)]
+    pub state: Option<BehaviorState>, // behavioural state (role), used with the state machine
+}
+
+// --- Scenario Struct Definition ---
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct Scenario {
+    #[serde(default)]
+    pub seed: Option<u64>, // seed of the simulator's random number generator
+    #[serde(default)]
+    pub boids: Vec<ScenarioBoid>,
+    #[serde(default)]
+    pub timeline: Vec<TimelineEvent>, // scripted events (JSON scenarios only)
+}
+
+// --- Scenario Implementation ---
+
+impl Scenario {
+    /// Loads a scenario file: JSON (an object with `boids` and an optional `seed`)
+    /// or CSV (a header naming the columns x, y, vx, vy and optionally group and state,
+    /// then one boid per row), chosen by the file extension.
+    ///
+    /// # Arguments
+    ///
+    /// * 'path' - The path of the scenario file.
+    ///
+    /// # Returns
+    ///
+    /// * Result<Scenario, Box<dyn std::error::Error>> - The scenario, or an error if the
+    ///   file cannot be read or parsed.
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Scenario, Box<dyn std::error::Error>> {
+        let path = path.as_ref();
+        let contents = std::fs::read_to_string(path)?;
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some("json") => Ok(serde_json::from_str(&contents)?),
+            Some("csv") => Ok(Scenario {
+                seed: None,
+                boids: parse_csv(&contents)?,
+                timeline: Vec::new(),
+            }),
+            _ => Err(format!("unknown scenario format '{}'", path.display()).into()),
+        }
+    }
+}
+
+/// Formats the state of the boids as a CSV scenario, which can be loaded back.
+///
+/// # Arguments
+///
+/// * 'boids' - A slice of the boids.
+///
+/// # Returns
+///
+/// * 'String' - The CSV text, header included.
+pub fn to_csv(boids: &[Boid]) -> String {
+    let mut csv = "x,y,vx,vy,group,state\n".to_string();
+    for boid in boids {
+        let group = boid
+            .group
+            .map(|group| group.to_string())
+            .unwrap_or_default();
+        csv += &format!(
+            "{},{},{},{},{},{}\n",
+            boid.pos.x,
+            boid.pos.y,
+            boid.vel.x,
+            boid.vel.y,
+            group,
+            boid.behavior.name()
+        );
+    }
+    csv
+}
+
+/// Parses the boids of a CSV scenario. Empty group and state cells are unset.
+fn parse_csv(contents: &str) -> Result<Vec<ScenarioBoid>, Box<dyn std::error::Error>> {
+    let mut lines = contents
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty());
+    let (_, header) = lines.next().ok_or("empty scenario file")?;
+    let columns: Vec<&str> = header.split(',').map(str::trim).collect();
+    let column = |name: &str| columns.iter().position(|c| *c == name);
+    let required = |name: &str| column(name).ok_or(format!("missing column '{}'", name));
+    let (x, y, vx, vy) = (
+        required("x")?,
+        required("y")?,
+        required("vx")?,
+        required("vy")?,
+    );
+    let (group, state) = (column("group"), column("state"));
+
+    let mut boids = Vec::new();
+    for (index, line) in lines {
+        let line_number = index + 1;
+        let cells: Vec<&str> = line.split(',').map(str::trim).collect();
+        let cell = |column: Option<usize>| {
+            column
+                .and_then(|c| cells.get(c))
+                .copied()
+                .filter(|cell| !cell.is_empty())
+        };
+        let number = |c: usize| -> Result<f32, String> {
+            let text = cell(Some(c)).ok_or(format!("line {}: missing value", line_number))?;
+            text.parse()
+                .map_err(|_| format!("line {}: invalid number '{}'", line_number, text))
+        };
+        boids.push(ScenarioBoid {
+            x: number(x)?,
+            y: number(y)?,
+            vx: number(vx)?,
+            vy: number(vy)?,
+            group: match cell(group) {
+                Some(text) => Some(
+                    text.parse()
+                        .map_err(|_| format!("line {}: invalid group '{}'", line_number, text))?,
+                ),
+                None => None,
+            },
+            state: match cell(state) {
+                Some(text) => Some(
+                    BehaviorState::ALL
+                        .into_iter()
+                        .find(|s| s.name() == text)
+                        .ok_or(format!("line {}: unknown state '{}'", line_number, text))?,
+                ),
+                None => None,
+            },
+        });
+    }
+    Ok(boids)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ggez::glam::Vec2;
+
+    #[test]
+    fn csv_round_trip_keeps_every_boid() {
+        let boids = vec![
+            Boid {
+                group: Some(2),
+                behavior: BehaviorState::Fleeing,
+                ..Boid::at(Vec2::new(12.5, -3.25), Vec2::new(0.1, 1.7))
+            },
+            Boid {
+                group: None,
+                behavior: BehaviorState::Resting,
+                ..Boid::at(Vec2::new(1.0 / 3.0, 640.0), Vec2::new(-2.0, 0.0))
+            },
+        ];
+        let parsed = parse_csv(&to_csv(&boids)).unwrap();
+        assert_eq!(parsed.len(), boids.len());
+        for (boid, loaded) in boids.iter().zip(&parsed) {
+            assert_eq!(Vec2::new(loaded.x, loaded.y), boid.pos);
+            assert_eq!(Vec2::new(loaded.vx, loaded.vy), boid.vel);
+            assert_eq!(loaded.group, boid.group);
+            assert_eq!(loaded.state, Some(boid.behavior));
+        }
+    }
+
+    #[test]
+    fn optional_columns_may_be_left_out() {
+        let parsed = parse_csv("vx,vy,x,y\n1,2,3,4\n\n").unwrap();
+        assert_eq!(parsed.len(), 1);
+        assert_eq!((parsed[0].x, parsed[0].y), (3.0, 4.0));
+        assert_eq!((parsed[0].vx, parsed[0].vy), (1.0, 2.0));
+        assert!(parsed[0].group.is_none() && parsed[0].state.is_none());
+    }
+
+    #[test]
+    fn bad_rows_are_reported_with_their_line() {
+        let error = parse_csv("x,y,vx,vy,state\n0,0,0,0,flocking\n0,0,0,0,dancing\n")
+            .unwrap_err()
+            .to_string();
+        assert_eq!(error, "line 3: unknown state 'dancing'");
+        assert!(parse_csv("x,y,vx\n0,0,0\n").is_err());
+    }
+}
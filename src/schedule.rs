@@ -0,0 +1,192 @@
+// src/schedule.rs
+// Parameter schedules: a numeric boids_config parameter can follow piecewise-linear
+// keyframes or a sinusoidal oscillation instead of a constant, evaluated at every
+// step (e.g. slowly sweeping matchingfactor up and down to map hysteresis).
+
+use crate::config::ScheduleConfig;
+
+// --- ScheduleConfig Implementation ---
+
+impl ScheduleConfig {
+    /// Checks the schedule and sorts its keyframes by step.
+    ///
+    /// # Returns
+    ///
+    /// * Result<ScheduleConfig, String> - The schedule, or an error if it has no
+    ///   keyframes or a period that is not positive.
+    pub fn validated(mut self) -> Result<ScheduleConfig, String> {
+        match &mut self {
+            ScheduleConfig::Keyframes { keys, .. } => {
+                if keys.is_empty() {
+                    return Err("keyframes schedule without keys".to_string());
+                }
+                keys.sort_by_key(|&(step, _)| step); // Stable: keys of a step keep their order
+            }
+            ScheduleConfig::Sine { period, .. } => {
+                if *period <= 0.0 {
+                    return Err(format!(
+                        "sine schedule period must be positive, got {}",
+                        period
+                    ));
+                }
+            }
+        }
+        Ok(self)
+    }
+
+    /// Returns the smallest and largest values the schedule takes.
+    ///
+    /// # Returns
+    ///
+    /// * '(f32, f32)' - The range of the scheduled values.
+    pub fn range(&self) -> (f32, f32) {
+        match self {
+            ScheduleConfig::Keyframes { keys, .. } => keys.iter().fold(
+                (f32::INFINITY, f32::NEG_INFINITY),
+                |(min, max), &(_, value)| (min.min(value), max.max(value)),
+            ),
+            ScheduleConfig::Sine {
+                mean, amplitude, ..
+            } => (mean - amplitude.abs(), mean + amplitude.abs()),
+        }
+    }
+
+    /// Returns the value of the parameter at a step.
+    ///
+    /// # Arguments
+    ///
+    /// * 'step' - The simulation step (0 before the first update).
+    ///
+    /// # Returns
+    ///
+    /// * 'f32' - The scheduled value.
+    pub fn value_at(&self, step: u64) -> f32 {
+        match self {
+            ScheduleConfig::Keyframes { keys, repeat } => {
+                let (Some(&(first_step, first)), Some(&(last_step, last))) =
+                    (keys.first(), keys.last())
+                else {
+                    return 0.0;
+                };
+                let step = if *repeat && last_step > first_step && step > last_step {
+                    first_step + (step - first_step) % (last_step - first_step)
+                } else {
+                    step
+                };
+                if step <= first_step {
+                    return first;
+                }
+                for pair in keys.windows(2) {
+                    let ((s0, v0), (s1, v1)) = (pair[0], pair[1]);
+                    if step <= s1 {
+                        let t = (step - s0) as f32 / (s1 - s0).max(1) as f32;
+                        return v0 + (v1 - v0) * t;
+                    }
+                }
+                last
+            }
+            ScheduleConfig::Sine {
+                mean,
+                amplitude,
+                period,
+                phase,
+            } => {
+                let angle = step as f32 / period * 2.0 * std::f32::consts::PI + phase.to_radians();
+                mean + amplitude * angle.sin()
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn keyframes(keys: &[(u64, f32)], repeat: bool) -> ScheduleConfig {
+        ScheduleConfig::Keyframes {
+            keys: keys.to_vec(),
+            repeat,
+        }
+        .validated()
+        .unwrap()
+    }
+
+    fn assert_close(actual: f32, expected: f32) {
+        assert!(
+            (actual - expected).abs() < 1e-5,
+            "expected {}, got {}",
+            expected,
+            actual
+        );
+    }
+
+    #[test]
+    fn keyframes_interpolate_linearly() {
+        // Given out of order: validated() sorts them
+        let schedule = keyframes(&[(20, 3.0), (10, 1.0), (40, -1.0)], false);
+        assert_close(schedule.value_at(10), 1.0);
+        assert_close(schedule.value_at(15), 2.0);
+        assert_close(schedule.value_at(20), 3.0);
+        assert_close(schedule.value_at(30), 1.0);
+        assert_close(schedule.value_at(40), -1.0);
+        assert_eq!(schedule.range(), (-1.0, 3.0));
+    }
+
+    #[test]
+    fn keyframes_hold_outside_their_steps() {
+        let schedule = keyframes(&[(10, 1.0), (20, 3.0)], false);
+        assert_close(schedule.value_at(0), 1.0);
+        assert_close(schedule.value_at(9), 1.0);
+        assert_close(schedule.value_at(21), 3.0);
+        assert_close(schedule.value_at(1_000_000), 3.0);
+    }
+
+    #[test]
+    fn repeated_keyframes_wrap_around() {
+        // One cycle spans steps 10..40
+        let schedule = keyframes(&[(10, 1.0), (20, 3.0), (40, -1.0)], true);
+        assert_close(schedule.value_at(5), 1.0); // before the first key: held
+        assert_close(schedule.value_at(40), -1.0); // the last key itself
+        assert_close(schedule.value_at(41), 1.0 + 2.0 * 0.1);
+        assert_close(schedule.value_at(55), 2.0); // same as step 25
+        assert_close(schedule.value_at(70), 1.0); // back at the first key
+        assert_close(schedule.value_at(10 + 30 * 7 + 15), 2.0);
+    }
+
+    #[test]
+    fn sine_starts_at_its_phase() {
+        let sine = |phase: f32| ScheduleConfig::Sine {
+            mean: 2.0,
+            amplitude: 0.5,
+            period: 100.0,
+            phase,
+        };
+        let schedule = sine(0.0);
+        assert_close(schedule.value_at(0), 2.0);
+        assert_close(schedule.value_at(25), 2.5);
+        assert_close(schedule.value_at(75), 1.5);
+        assert_close(schedule.value_at(100), 2.0);
+
+        // A quarter turn of phase shifts the wave by a quarter period
+        let shifted = sine(90.0);
+        assert_close(shifted.value_at(0), 2.5);
+        assert_close(shifted.value_at(50), 1.5);
+        assert_eq!(shifted.range(), (1.5, 2.5));
+    }
+
+    #[test]
+    fn invalid_schedules_are_rejected() {
+        let empty = ScheduleConfig::Keyframes {
+            keys: Vec::new(),
+            repeat: false,
+        };
+        assert!(empty.validated().is_err());
+        let flat = ScheduleConfig::Sine {
+            mean: 0.0,
+            amplitude: 1.0,
+            period: 0.0,
+            phase: 0.0,
+        };
+        assert!(flat.validated().is_err());
+    }
+}
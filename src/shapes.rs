@@ -0,0 +1,204 @@
+// src/shapes.rs
+// Geometric shapes (circles and axis-aligned rectangles) shared by obstacles and
+// other world elements, with signed distance queries.
+
+use ggez::glam::Vec2;
+
+use crate::config::ShapeConfig;
+
+// --- Shape Enum Definition ---
+
+#[derive(Debug, Clone, Copy)]
+pub enum Shape {
+    Circle { center: Vec2, radius: f32 },
+    Rect { center: Vec2, half_size: Vec2 },
+}
+
+// --- Shape Implementation ---
+
+impl Shape {
+    /// Creates a new Shape from its configuration.
+    ///
+    /// # Arguments
+    ///
+    /// * 'config' - The ShapeConfig read from boids.yaml.
+    ///
+    /// # Returns
+    ///
+    /// * 'Self' - A new Shape instance.
+    pub fn new(config: &ShapeConfig) -> Self {
+        match *config {
+            ShapeConfig::Circle { x, y, radius } => Shape::Circle {
+                center: Vec2::new(x, y),
+                radius,
+            },
+            ShapeConfig::Rectangle {
+                x,
+                y,
+                width,
+                height,
+            } => Shape::Rect {
+                center: Vec2::new(x, y),
+                half_size: Vec2::new(width, height) * 0.5,
+            },
+        }
+    }
+
+    /// Returns the center of the shape.
+    pub fn center(&self) -> Vec2 {
+        match *self {
+            Shape::Circle { center, .. } | Shape::Rect { center, .. } => center,
+        }
+    }
+
+    /// Moves the shape so that its center is at the given position.
+    pub fn set_center(&mut self, pos: Vec2) {
+        match self {
+            Shape::Circle { center, .. } | Shape::Rect { center, .. } => *center = pos,
+        }
+    }
+
+    /// Returns the signed distance from a point to the shape's boundary
+    /// (negative inside the shape).
+    ///
+    /// # Arguments
+    ///
+    /// * 'p' - The point to measure from.
+    pub fn signed_distance(&self, p: Vec2) -> f32 {
+        match *self {
+            Shape::Circle { center, radius } => p.distance(center) - radius,
+            Shape::Rect { center, half_size } => {
+                let d = (p - center).abs() - half_size;
+                d.max(Vec2::ZERO).length() + d.x.max(d.y).min(0.0)
+            }
+        }
+    }
+
+    /// Returns the outward unit normal of the boundary closest to a point,
+    /// i.e. the direction in which the signed distance grows fastest.
+    ///
+    /// # Arguments
+    ///
+    /// * 'p' - The point to measure from.
+    pub fn normal(&self, p: Vec2) -> Vec2 {
+        match *self {
+            Shape::Circle { center, .. } => (p - center).try_normalize().unwrap_or(Vec2::X),
+            Shape::Rect { center, half_size } => {
+                let local = p - center;
+                let d = local.abs() - half_size;
+                if d.x > 0.0 || d.y > 0.0 {
+                    // Outside: direction from the closest point of the rectangle
+                    let closest = local.clamp(-half_size, half_size);
+                    (local - closest).try_normalize().unwrap_or(Vec2::X)
+                } else if d.x > d.y {
+                    // Inside, closest to a vertical side
+                    Vec2::new(local.x.signum(), 0.0)
+                } else {
+                    // Inside, closest to a horizontal side
+                    Vec2::new(0.0, local.y.signum())
+                }
+            }
+        }
+    }
+
+    /// Returns the axis-aligned bounding box of the shape as (min, max) corners.
+    pub fn bounds(&self) -> (Vec2, Vec2) {
+        match *self {
+            Shape::Circle { center, radius } => (center - radius, center + radius),
+            Shape::Rect { center, half_size } => (center - half_size, center + half_size),
+        }
+    }
+
+    /// Returns true if the segment from a to b touches the shape.
+    ///
+    /// # Arguments
+    ///
+    /// * 'a', 'b' - The end points of the segment.
+    pub fn intersects_segment(&self, a: Vec2, b: Vec2) -> bool {
+        let ab = b - a;
+        match *self {
+            Shape::Circle { center, radius } => {
+                // Distance from the center to the closest point of the segment
+                let len_sq = ab.length_squared();
+                let t = if len_sq > 1e-12 {
+                    ((center - a).dot(ab) / len_sq).clamp(0.0, 1.0)
+                } else {
+                    0.0
+                };
+                (a + ab * t).distance_squared(center) <= radius * radius
+            }
+            Shape::Rect { center, half_size } => {
+                // Slab test (Liang-Barsky) on the segment parameter t in [0, 1]
+                let (min, max) = (center - half_size, center + half_size);
+                let mut t_min: f32 = 0.0;
+                let mut t_max: f32 = 1.0;
+                for axis in 0..2 {
+                    if ab[axis].abs() < 1e-9 {
+                        if a[axis] < min[axis] || a[axis] > max[axis] {
+                            return false; // Parallel to the slab and outside it
+                        }
+                        continue;
+                    }
+                    let t1 = (min[axis] - a[axis]) / ab[axis];
+                    let t2 = (max[axis] - a[axis]) / ab[axis];
+                    t_min = t_min.max(t1.min(t2));
+                    t_max = t_max.min(t1.max(t2));
+                    if t_min > t_max {
+                        return false;
+                    }
+                }
+                true
+            }
+        }
+    }
+
+    /// Returns the distance along a ray to the first point of the shape, if hit within max_dist.
+    /// A ray starting inside the shape hits it immediately.
+    ///
+    /// # Arguments
+    ///
+    /// * 'origin' - Start of the ray.
+    /// * 'dir' - Unit direction of the ray.
+    /// * 'max_dist' - Length of the ray.
+    pub fn ray_distance(&self, origin: Vec2, dir: Vec2, max_dist: f32) -> Option<f32> {
+        if self.signed_distance(origin) < 0.0 {
+            return Some(0.0);
+        }
+        let t = match *self {
+            Shape::Circle { center, radius } => {
+                // Solve |origin + t * dir - center| = radius for the smallest t
+                let oc = origin - center;
+                let b = oc.dot(dir);
+                let c = oc.length_squared() - radius * radius;
+                let discriminant = b * b - c;
+                if discriminant < 0.0 {
+                    return None;
+                }
+                -b - discriminant.sqrt()
+            }
+            Shape::Rect { center, half_size } => {
+                // Slab test on the ray parameter
+                let (min, max) = (center - half_size, center + half_size);
+                let mut t_min = f32::NEG_INFINITY;
+                let mut t_max = f32::INFINITY;
+                for axis in 0..2 {
+                    if dir[axis].abs() < 1e-9 {
+                        if origin[axis] < min[axis] || origin[axis] > max[axis] {
+                            return None;
+                        }
+                        continue;
+                    }
+                    let t1 = (min[axis] - origin[axis]) / dir[axis];
+                    let t2 = (max[axis] - origin[axis]) / dir[axis];
+                    t_min = t_min.max(t1.min(t2));
+                    t_max = t_max.min(t1.max(t2));
+                }
+                if t_min > t_max {
+                    return None;
+                }
+                t_min
+            }
+        };
+        (0.0..=max_dist).contains(&t).then_some(t)
+    }
+}
@@ -5,11 +5,42 @@
 use ggez::glam::Vec2; // Use glam::Vec2 for positions
 use kdtree::distance::squared_euclidean; // Use squared Euclidean distance for KDTree
 use kdtree::KdTree; // Import the KDTree structure
-use rand::Rng; // Import Rng for random placement
+use rand::rngs::StdRng; // Simulator-owned RNG (reproduction)
+use rand::{Rng, SeedableRng}; // Import Rng for random placement
 use rayon::prelude::*; // Import rayon for parallel iterators
+use std::collections::{HashMap, HashSet, VecDeque}; // Boid lookup by id (formations), flock centers (roosting), step history
+use uuid::Uuid; // Boid ids
 
+use crate::behavior::{self, Senses, StateMachine}; // Behavioural state machine
 use crate::boids::Boid; // Import the Boid struct
-use crate::config::BoidsConfig; // Import the boid configuration
+use crate::config::{
+    AvoidanceConfig, BehaviorState, BoidsConfig, CollisionConfig, EvolutionConfig, FormationConfig,
+    GroupsConfig, InteractionConfig, LifeConfig, Param, RoostingConfig, ScheduleConfig,
+    SpawnConfig, StartleConfig,
+};
+use crate::flow::{Emitter, Sink}; // Through-flow emitters and sinks
+use crate::food::{self, FoodSource}; // Food sources replenishing energy
+use crate::formation::{self, Formation}; // Formation flight
+use crate::genetics::{GenerationStats, Genes, tournament_select}; // Heritable parameters
+use crate::groups; // Family groups
+use crate::morph::Morph; // Shape morphing
+use crate::obstacles::{self, Obstacle, ObstacleGrid}; // Static and moving obstacles
+use crate::orca; // Reciprocal collision avoidance
+use crate::perception::{self, PastStates}; // Sensory delay and perception noise
+use crate::pheromone::PheromoneGrid; // Pheromone field
+use crate::projection; // Projection-based interaction
+use crate::roost::{self, Roost, RoostState}; // Roosting day cycle
+use crate::scenario::{self, Scenario}; // Exact initial conditions
+use crate::spawn; // Spawn patterns (timeline spawns)
+use crate::startle::{self, AlarmState, Exposure}; // Startle cascades
+use crate::stats::{CascadeStats, FoodPatchStats, IntakeStats, StartleTrigger, StepStats};
+use crate::timeline::{Attractor, Rule, Timeline, TimelineAction}; // Scripted events
+use crate::ttc; // Time-to-collision avoidance
+use crate::world_map::WorldMap; // Bitmap world map (walls, goals, regions)
+use crate::zones::Zone; // Parameter override zones
+
+// --- Constants ---
+const HISTORY_LEN: usize = 1000; // Steps of statistics kept, older ones are dropped
 
 // --- BoidSimulator Struct Definition ---
 
@@ -17,8 +48,36 @@ pub struct BoidSimulator {
     pub boids: Vec<Boid>,    // Vector holding all the Boid instances
     config: BoidsConfig,     // Simulation parameters for boids
     screen_dims: (f32, f32), // Screen width and height
-    kdtree: KdTree<f32, usize, [f32; 2]>, // KDTree for efficient neighbor search
-                             // Stores boid indices (usize) associated with positions ([f32; 2])
+    // KDTree for efficient neighbor search
+    // Stores boid indices (usize) associated with positions ([f32; 2])
+    kdtree: KdTree<f32, usize, [f32; 2]>,
+    food: Vec<FoodSource>, // Food sources (life cycle, evolution or foraging)
+    obstacles: Vec<Obstacle>, // Static and moving obstacles
+    obstacle_grid: ObstacleGrid, // Grid for line of sight tests (occlusion only)
+    world_map: Option<WorldMap>, // Walls, goals and regions loaded from a bitmap
+    zones: Vec<Zone>,      // Zones overriding the boid parameters
+    pheromone: Option<PheromoneGrid>, // Pheromone field (when enabled)
+    formations: Vec<Formation>, // Leaders and members of every formation
+    formed: HashSet<Uuid>, // Boids present when the formations were last formed
+    morph: Option<Morph>,  // Target shape timeline (when enabled)
+    roosts: Vec<Roost>,    // Roosts of the day cycle
+    emitters: Vec<Emitter>, // Emitters spawning boids during the run
+    sinks: Vec<Sink>,      // Sinks removing the boids entering them
+    behavior: Option<StateMachine>, // Behavioural state machine (when enabled)
+    past_states: PastStates, // Recent boid states, for the perception delay
+    next_group: usize,     // Id of the next family group formed
+    timeline: Timeline,    // Scripted events of the scenario
+    schedules: Vec<(String, Param, ScheduleConfig)>, // Parameters following a schedule, by name
+    disabled_rules: [bool; Rule::COUNT], // Flocking rules switched off by the timeline
+    pause_requested: bool, // Set by a pause event, cleared by take_pause_request
+    rng: StdRng,           // RNG used during updates (offspring placement)
+    step: u64,             // Number of update steps performed
+    pub history: VecDeque<StepStats>, // Statistics of the last HISTORY_LEN steps
+    pub generations: Vec<GenerationStats>, // Gene distribution of every finished generation
+    fallen: Vec<Boid>,     // Boids caught by the predators during the current generation
+    cascades: HashMap<usize, CascadeStats>, // Startle cascades still propagating, by id
+    next_cascade: usize,   // Id of the next startle cascade
+    finished_cascades: Vec<CascadeStats>, // Cascades finished since the last take_finished_cascades
 }
 
 // --- BoidSimulator Implementation ---
@@ -35,12 +94,42 @@ impl BoidSimulator {
     ///
     /// * `Self` - A new BoidSimulator instance.
     pub fn new(config: BoidsConfig, screen_dims: (f32, f32)) -> Self {
+        let pheromone = config
+            .pheromone
+            .map(|pheromone| PheromoneGrid::new(screen_dims, pheromone.cell_size));
         BoidSimulator {
             boids: Vec::new(), // Start with an empty vector of boids
             config,
             screen_dims,
             // Initialize an empty KDTree with 2 dimensions (x, y)
             kdtree: KdTree::new(2),
+            food: Vec::new(),
+            obstacles: Vec::new(),
+            obstacle_grid: ObstacleGrid::default(),
+            world_map: None,
+            zones: Vec::new(),
+            pheromone,
+            formations: Vec::new(),
+            formed: HashSet::new(),
+            morph: None,
+            roosts: Vec::new(),
+            emitters: Vec::new(),
+            sinks: Vec::new(),
+            behavior: None,
+            past_states: PastStates::default(),
+            next_group: 0,
+            timeline: Timeline::default(),
+            schedules: Vec::new(),
+            disabled_rules: [false; Rule::COUNT],
+            pause_requested: false,
+            rng: StdRng::from_os_rng(),
+            step: 0,
+            history: VecDeque::with_capacity(HISTORY_LEN),
+            generations: Vec::new(),
+            fallen: Vec::new(),
+            cascades: HashMap::new(),
+            next_cascade: 0,
+            finished_cascades: Vec::new(),
         }
     }
 
@@ -52,7 +141,356 @@ impl BoidSimulator {
     /// * `pos` - The initial position Vec2 for the new boid.
     /// * `rng` - A mutable reference to a random number generator.
     pub fn add_boid(&mut self, pos: Vec2, rng: &mut impl Rng) {
-        self.boids.push(Boid::new(pos, rng));
+        let genes = Genes::from_config(&self.config);
+        self.boids.push(self.spawn_boid(pos, genes, rng));
+    }
+
+    /// Adds a new Boid to the simulation with a given initial velocity.
+    ///
+    /// # Arguments
+    ///
+    /// * `pos` - The initial position Vec2 for the new boid.
+    /// * `vel` - The initial velocity Vec2 for the new boid.
+    /// * `rng` - A mutable reference to a random number generator.
+    pub fn add_boid_with_velocity(&mut self, pos: Vec2, vel: Vec2, rng: &mut impl Rng) {
+        let genes = Genes::from_config(&self.config);
+        let mut boid = self.spawn_boid(pos, genes, rng);
+        boid.vel = vel;
+        self.boids.push(boid);
+    }
+
+    /// Adds the boids of a scenario with their exact initial state. Call after
+    /// set_behavior, which would reset their behavioural states.
+    ///
+    /// # Arguments
+    ///
+    /// * `scenario` - The Scenario loaded from a scenario file.
+    /// * `rng` - A mutable reference to a random number generator.
+    pub fn add_scenario(&mut self, scenario: &Scenario, rng: &mut impl Rng) {
+        for state in &scenario.boids {
+            let pos = Vec2::new(state.x, state.y);
+            let vel = Vec2::new(state.vx, state.vy);
+            self.add_boid_with_velocity(pos, vel, rng);
+            if let Some(boid) = self.boids.last_mut() {
+                boid.group = state.group;
+                if let Some(behavior) = state.state {
+                    boid.behavior = behavior;
+                }
+            }
+        }
+        // New groups must not reuse the ids of the scenario's groups
+        let last_group = self.boids.iter().filter_map(|boid| boid.group).max();
+        self.next_group = self.next_group.max(last_group.map_or(0, |group| group + 1));
+    }
+
+    /// Sets the scripted events applied during the run.
+    ///
+    /// # Arguments
+    ///
+    /// * `timeline` - The Timeline built from the scenario.
+    pub fn set_timeline(&mut self, timeline: Timeline) {
+        self.timeline = timeline;
+    }
+
+    /// Sets the schedules the numeric parameters follow during the run.
+    ///
+    /// # Arguments
+    ///
+    /// * `schedules` - The schedules, by parameter name.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<(), String>` - An error if a name is not a numeric parameter, or is
+    ///   inherited by the boids under evolution (a schedule would have no effect).
+    pub fn set_schedules(
+        &mut self,
+        schedules: Vec<(String, ScheduleConfig)>,
+    ) -> Result<(), String> {
+        let mut resolved = Vec::new();
+        for (name, schedule) in schedules {
+            if self.config.evolution.is_some() && Genes::NAMES.contains(&name.as_str()) {
+                return Err(format!(
+                    "cannot schedule '{}' with evolution enabled: the boids inherit {} \
+                     from their parents",
+                    name,
+                    Genes::NAMES.join(", ")
+                ));
+            }
+            let param = self.config.param(&name)?;
+            self.apply_param(&name, param, schedule.value_at(self.step));
+            resolved.push((name, param, schedule));
+        }
+        self.schedules = resolved;
+        Ok(())
+    }
+
+    /// Returns the simulation parameters currently in effect.
+    pub fn config(&self) -> &BoidsConfig {
+        &self.config
+    }
+
+    /// Sets a numeric simulation parameter by name. Without evolution the boids'
+    /// flocking parameters follow the config, with evolution they are inherited and
+    /// only the boids created from the config later get the new value.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name or dotted path of the parameter, as written in boids.yaml.
+    /// * `value` - The new value.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<(), String>` - An error if there is no numeric parameter of that name,
+    ///   or the value does not fit its type.
+    pub fn set_param(&mut self, name: &str, value: f32) -> Result<(), String> {
+        let param = self.config.param(name)?;
+        param
+            .check_range(value, value)
+            .map_err(|e| format!("{}: {}", name, e))?;
+        self.apply_param(name, param, value);
+        Ok(())
+    }
+
+    /// Sets a resolved numeric parameter, and the boids' genes when they follow it.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name or dotted path of the parameter.
+    /// * `param` - The parameter, resolved from its name.
+    /// * `value` - The new value, within the range the parameter accepts.
+    fn apply_param(&mut self, name: &str, param: Param, value: f32) {
+        param.set(&mut self.config, value);
+        if self.config.evolution.is_none() && Genes::NAMES.contains(&name) {
+            let genes = Genes::from_config(&self.config);
+            for boid in self.boids.iter_mut() {
+                boid.genes = genes;
+            }
+        }
+    }
+
+    /// Switches a flocking rule on or off.
+    ///
+    /// # Arguments
+    ///
+    /// * `rule` - The rule.
+    /// * `enabled` - Whether the rule applies.
+    pub fn set_rule(&mut self, rule: Rule, enabled: bool) {
+        self.disabled_rules[rule.index()] = !enabled;
+    }
+
+    /// Returns true once after a pause event, for the windowed simulation to pause.
+    pub fn take_pause_request(&mut self) -> bool {
+        std::mem::take(&mut self.pause_requested)
+    }
+
+    /// Seeds the random number generator used during updates, for reproducible runs.
+    ///
+    /// # Arguments
+    ///
+    /// * `seed` - The seed.
+    pub fn set_seed(&mut self, seed: u64) {
+        self.rng = StdRng::seed_from_u64(seed);
+    }
+
+    /// Creates a boid with the starting energy of the life cycle, if enabled.
+    fn spawn_boid(&self, pos: Vec2, genes: Genes, rng: &mut impl Rng) -> Boid {
+        let mut boid = Boid::new(pos, genes, rng);
+        if let Some(life) = &self.config.life {
+            boid.energy = life.initial_energy;
+        }
+        if let Some(machine) = &self.behavior {
+            boid.behavior = machine.initial();
+        }
+        boid
+    }
+
+    /// Adds a food source to the simulation.
+    ///
+    /// # Arguments
+    ///
+    /// * `source` - The FoodSource to add.
+    pub fn add_food_source(&mut self, source: FoodSource) {
+        self.food.push(source);
+    }
+
+    /// Adds an obstacle to the simulation.
+    ///
+    /// # Arguments
+    ///
+    /// * `obstacle` - The Obstacle to add.
+    pub fn add_obstacle(&mut self, obstacle: Obstacle) {
+        self.obstacles.push(obstacle);
+    }
+
+    /// Sets the world map whose walls, goals and regions the boids react to.
+    ///
+    /// # Arguments
+    ///
+    /// * `world_map` - The WorldMap loaded from the map bitmap.
+    pub fn set_world_map(&mut self, world_map: WorldMap) {
+        self.world_map = Some(world_map);
+    }
+
+    /// Adds a zone overriding the boid parameters.
+    ///
+    /// # Arguments
+    ///
+    /// * `zone` - The Zone to add.
+    pub fn add_zone(&mut self, zone: Zone) {
+        self.zones.push(zone);
+    }
+
+    /// Sets the timeline of target shapes the boids morph into.
+    ///
+    /// # Arguments
+    ///
+    /// * `morph` - The Morph built from the morph stages.
+    pub fn set_morph(&mut self, morph: Morph) {
+        self.morph = Some(morph);
+    }
+
+    /// Adds a roost the boids return to at night.
+    ///
+    /// # Arguments
+    ///
+    /// * `roost` - The Roost to add.
+    pub fn add_roost(&mut self, roost: Roost) {
+        self.roosts.push(roost);
+    }
+
+    /// Sets the behavioural state machine of the boids, putting every boid in its
+    /// initial state.
+    ///
+    /// # Arguments
+    ///
+    /// * `machine` - The StateMachine built from the behavior config.
+    pub fn set_behavior(&mut self, machine: StateMachine) {
+        for boid in self.boids.iter_mut() {
+            boid.behavior = machine.initial();
+            boid.behavior_steps = 0;
+        }
+        self.behavior = Some(machine);
+    }
+
+    /// Adds an emitter spawning boids during the run.
+    ///
+    /// # Arguments
+    ///
+    /// * `emitter` - The Emitter to add.
+    pub fn add_emitter(&mut self, emitter: Emitter) {
+        self.emitters.push(emitter);
+    }
+
+    /// Adds a sink removing the boids entering it.
+    ///
+    /// # Arguments
+    ///
+    /// * `sink` - The Sink to add.
+    pub fn add_sink(&mut self, sink: Sink) {
+        self.sinks.push(sink);
+    }
+
+    /// Returns the emitters.
+    pub fn emitters(&self) -> &[Emitter] {
+        &self.emitters
+    }
+
+    /// Returns the sinks.
+    pub fn sinks(&self) -> &[Sink] {
+        &self.sinks
+    }
+
+    /// Returns the roosts.
+    pub fn roosts(&self) -> &[Roost] {
+        &self.roosts
+    }
+
+    /// Returns the parameter override zones.
+    pub fn zones(&self) -> &[Zone] {
+        &self.zones
+    }
+
+    /// Returns the pheromone field, if enabled.
+    pub fn pheromone(&self) -> Option<&PheromoneGrid> {
+        self.pheromone.as_ref()
+    }
+
+    /// Returns the obstacles at their current positions.
+    pub fn obstacles(&self) -> &[Obstacle] {
+        &self.obstacles
+    }
+
+    /// Returns the depletion state of every food source.
+    pub fn food_patch_stats(&self) -> Vec<FoodPatchStats> {
+        self.food
+            .iter()
+            .enumerate()
+            .map(|(patch, source)| FoodPatchStats {
+                step: self.step,
+                patch,
+                amount: source.amount,
+                consumed: source.consumed,
+                empty_steps: source.empty_steps,
+                feeders: source.feeders,
+            })
+            .collect()
+    }
+
+    /// Returns the foraging success of every boid along with its number of
+    /// neighbors and the size of its flock.
+    pub fn intake_stats(&mut self) -> Vec<IntakeStats> {
+        let labels = self.flock_labels(); // Also rebuilds the KDTree
+        let mut flock_sizes = vec![0; self.boids.len()];
+        for &label in &labels {
+            flock_sizes[label] += 1;
+        }
+        let visible_range_sq = self.config.visible_range * self.config.visible_range;
+        self.boids
+            .iter()
+            .zip(&labels)
+            .map(|(boid, &label)| {
+                let neighbors = self
+                    .kdtree
+                    .within(
+                        &[boid.pos.x, boid.pos.y],
+                        visible_range_sq,
+                        &squared_euclidean,
+                    )
+                    .map_or(0, |found| found.len().saturating_sub(1)); // Minus the boid itself
+                IntakeStats {
+                    id: boid.id,
+                    age: boid.age,
+                    energy_gathered: boid.energy_gathered,
+                    neighbors,
+                    flock_size: flock_sizes[label],
+                }
+            })
+            .collect()
+    }
+
+    /// Returns the statistics of the last update step, if any.
+    pub fn latest_stats(&self) -> Option<&StepStats> {
+        self.history.back()
+    }
+
+    /// Removes every boid matching the predicate, keeping the order of the others,
+    /// and rebuilds the KDTree so that its stored indices stay valid.
+    ///
+    /// # Arguments
+    ///
+    /// * `predicate` - Returns true for the boids to remove.
+    ///
+    /// # Returns
+    ///
+    /// * `usize` - The number of boids removed.
+    fn remove_boids<F: FnMut(&Boid) -> bool>(&mut self, mut predicate: F) -> usize {
+        let before = self.boids.len();
+        self.boids.retain(|boid| !predicate(boid));
+        let removed = before - self.boids.len();
+        if removed > 0 {
+            self.build_kdtree();
+        }
+        removed
     }
 
     /// Rebuilds the KDTree based on the current positions of all boids.
@@ -74,17 +512,105 @@ impl BoidSimulator {
     }
 
     /// Updates the state of all boids for one simulation step.
-    /// 1. Rebuilds the KDTree for efficient neighbor finding.
+    /// 0. Moves the obstacles along their trajectories (and rebuilds the occlusion grid).
+    /// 1. Rebuilds the KDTree for efficient neighbor finding, advances the day cycle
+    ///    and updates the formation slots and the morph targets.
     /// 2. Calculates velocity changes for all boids based on neighbors.
     /// 3. Applies the calculated changes and updates positions.
+    /// 4. Resolves overlaps between boids when collisions are enabled,
+    ///    then pushes boids out of the obstacles, lands the boids reaching their roost
+    ///    at night, and updates the pheromone field.
+    /// 5. Shares the food between the feeding boids, then runs the life cycle
+    ///    (energy, death, reproduction) when enabled.
+    /// 6. Ends the current generation when evolution is enabled.
+    /// 7. Records the step statistics.
     pub fn update(&mut self) {
+        self.step += 1;
+
+        // Scheduled parameter values of this step (checked to fit when loaded)
+        let schedules = std::mem::take(&mut self.schedules);
+        for (name, param, schedule) in &schedules {
+            self.apply_param(name, *param, schedule.value_at(self.step));
+        }
+        self.schedules = schedules;
+
+        // Scripted events due at this step (after the schedules, so they win for the step)
+        for action in self.timeline.due(self.step) {
+            self.apply_event(action);
+        }
+
+        // Move the obstacles along their trajectories
+        let (dt, screen_dims) = (self.config.dt, self.screen_dims);
+        for obstacle in self.obstacles.iter_mut() {
+            obstacle.update(dt, screen_dims);
+        }
+        let occlusion = self.config.obstacle_occlusion && !self.obstacles.is_empty();
+        if occlusion {
+            self.obstacle_grid =
+                ObstacleGrid::new(&self.obstacles, screen_dims, self.config.visible_range);
+        }
+
         if self.boids.is_empty() {
-            return; // Nothing to update if there are no boids
+            // Nothing to update if there are no boids, but the emitters keep spawning
+            let emitted = self.update_emitters();
+            self.record_stats(StepStats {
+                emitted,
+                ..StepStats::default()
+            });
+            return;
         }
 
         // 1. Rebuild the KDTree with current boid positions
         self.build_kdtree();
 
+        // Remember the current states for the perception delay, and draw the seed of
+        // this step's perception noise
+        let perception_seed: u64 = match self.config.perception {
+            Some(perception) => {
+                if perception.delay > 0 {
+                    self.past_states.record(&self.boids, perception.delay);
+                }
+                self.rng.random()
+            }
+            None => 0,
+        };
+
+        // Family groups: formed at startup, then joined and left on the fly
+        if let Some(groups) = self.config.groups {
+            self.update_groups(&groups);
+        }
+
+        // Day cycle: take off at dawn, head for the roosts later in the day
+        let (homing, night) = match self.config.roosting {
+            Some(params) if !self.roosts.is_empty() => self.update_roosting(&params),
+            _ => (0.0, false),
+        };
+
+        // Slot of every boid flying in formation (target, leader velocity)
+        let (slots, formation_error) = match self.config.formation {
+            Some(formation) => self.update_formations(&formation),
+            None => (Vec::new(), 0.0),
+        };
+
+        // Target point of every boid while morphing
+        let targets: Vec<Option<Vec2>> = match self.morph.as_mut() {
+            Some(morph) => {
+                morph.update(self.step, &self.boids);
+                self.boids
+                    .iter()
+                    .map(|boid| morph.target_of(&boid.id))
+                    .collect()
+            }
+            None => Vec::new(),
+        };
+        // Mean distance of the targeted boids from their targets
+        let morph_error = mean(
+            self.boids
+                .iter()
+                .zip(&targets)
+                .filter_map(|(boid, target)| target.map(|target| boid.pos.distance(target))),
+        );
+
         // --- Parallel Calculation of Velocity Changes ---
         // Use rayon's par_iter to process boids in parallel
         // We collect the results into a new vector
@@ -93,17 +619,99 @@ impl BoidSimulator {
         let screen_dims = self.screen_dims; // Copy screen_dims
         let kdtree = &self.kdtree; // Immutable borrow for kdtree
         let boids_ref = &self.boids; // Immutable borrow of boids vector for neighbor lookup
+        let obstacles = &self.obstacles; // Immutable borrow of the obstacles
+        let obstacle_grid = &self.obstacle_grid; // Immutable borrow of the occlusion grid
+        let world_map = self.world_map.as_ref(); // Immutable borrow of the world map
+        let zones = &self.zones; // Immutable borrow of the override zones
+        let food_sources = &self.food; // Immutable borrow of the food sources
+        let pheromone = self.pheromone.as_ref(); // Immutable borrow of the pheromone field
+        let slots = &slots; // Immutable borrow of the formation slots
+        let morph = self.morph.as_ref(); // Immutable borrow of the morph timeline
+        let targets = &targets; // Immutable borrow of the morph targets
+        let roosts = &self.roosts; // Immutable borrow of the roosts
+        let machine = self.behavior.as_ref(); // Immutable borrow of the state machine
+        let past_states = &self.past_states; // Immutable borrow of the past states
+        let disabled_rules = self.disabled_rules; // Copy the rule switches
 
-        let velocity_changes: Vec<Vec2> = self
+        // Each boid gets its velocity change along with the parameters in effect where it is,
+        // its exposure to alarmed neighbors, its next behavioural state and the opacity of
+        // its visual field (projection model)
+        let velocity_changes: Vec<_> = self
             .boids
             .par_iter() // Create parallel iterators
             .enumerate() // Get index along with boid reference
-            .map(|(_i, current_boid)| {
+            .map(|(i, current_boid)| {
                 // Process each boid in parallel
                 let current_pos_arr = [current_boid.pos.x, current_boid.pos.y];
 
+                // Parameters in effect at the boid's position (map regions and zones
+                // override them, blended near the zone edges)
+                let mut local_config = *config;
+                let mut genes = current_boid.genes;
+                let region = world_map.and_then(|map| map.region_at(current_boid.pos));
+                if let Some(overrides) = region {
+                    overrides.apply(&mut local_config, &mut genes, 1.0);
+                }
+                for zone in zones {
+                    let weight = zone.weight_at(current_boid.pos);
+                    if weight > 0.0 {
+                        zone.overrides()
+                            .apply(&mut local_config, &mut genes, weight);
+                    }
+                }
+                // Escaping boids fly faster
+                let escape = current_boid.alarm.escape().map(|(_, escape)| escape);
+                if let (Some(_), Some(startle)) = (escape, &config.startle) {
+                    local_config.maxspeed *= startle.speed_boost;
+                }
+                // Rules weighted by the boid's behavioural state
+                let weights = machine.map(|machine| *machine.weights(current_boid.behavior));
+                if let Some(weights) = &weights {
+                    genes.avoidfactor *= weights.separation;
+                    genes.matchingfactor *= weights.alignment;
+                    genes.centeringfactor *= weights.cohesion;
+                    local_config.maxspeed *= weights.speed;
+                    local_config.minspeed *= weights.speed;
+                }
+                // Rules switched off by the timeline
+                if disabled_rules[Rule::Separation.index()] {
+                    genes.avoidfactor = 0.0;
+                }
+                if disabled_rules[Rule::Alignment.index()] {
+                    genes.matchingfactor = 0.0;
+                }
+                if disabled_rules[Rule::Cohesion.index()] {
+                    genes.centeringfactor = 0.0;
+                }
+                if disabled_rules[Rule::Boundary.index()] {
+                    local_config.turnfactor = 0.0;
+                }
+                // Boids slow down when approaching their roost at night, and stop once landed
+                let home = current_boid.roost.roost().map(|roost| &roosts[roost]);
+                if let (Some(roost), Some(params), true) = (home, &config.roosting, night) {
+                    local_config.maxspeed =
+                        roost.speed_limit(current_boid.pos, local_config.maxspeed, params);
+                    local_config.minspeed = local_config.minspeed.min(local_config.maxspeed);
+                }
+                if current_boid.roost.is_landed() {
+                    local_config.minspeed = 0.0;
+                }
+                // Morphing boids only keep their separation, and may come to a stop
+                let target = targets.get(i).copied().flatten();
+                if target.is_some() {
+                    genes.matchingfactor = 0.0;
+                    genes.centeringfactor = 0.0;
+                    local_config.minspeed = 0.0;
+                }
+                let config = &local_config;
+
                 // Find neighbors using the shared KDTree ( read-only )
-                let visible_range_sq = config.visible_range * config.visible_range;
+                // (the projection model looks at every boid in its range)
+                let visible_range = match config.interaction {
+                    InteractionConfig::Metric => genes.visible_range,
+                    InteractionConfig::Projection { range, .. } => range,
+                };
+                let visible_range_sq = visible_range * visible_range;
                 // Querying the KDTree should be thread safe for read
                 let neighbor_indices_with_dist = kdtree
                     .within(&current_pos_arr, visible_range_sq, &squared_euclidean)
@@ -114,22 +722,1359 @@ impl BoidSimulator {
                     .iter()
                     .map(|&(_dist_sq, &index)| &boids_ref[index])
                     .filter(|&neighbor| neighbor.id != current_boid.id)
+                    // Neighbors behind an obstacle are not seen
+                    .filter(|&neighbor| {
+                        !occlusion
+                            || obstacle_grid.line_of_sight(
+                                obstacles,
+                                current_boid.pos,
+                                neighbor.pos,
+                            )
+                    })
                     .collect();
 
+                // Neighbors as perceived: where they were some steps ago, blurred by noise
+                // (one noise stream per boid, so that runs do not depend on the threads)
+                let perceived: Vec<Boid> = match &config.perception {
+                    Some(perception) => {
+                        let mut rng = StdRng::seed_from_u64(perception_seed.wrapping_add(i as u64));
+                        neightbors
+                            .iter()
+                            .map(|neighbor| {
+                                perception::perceive(neighbor, past_states, perception, &mut rng)
+                            })
+                            .collect()
+                    }
+                    None => Vec::new(),
+                };
+                let neightbors: Vec<&Boid> = match config.perception {
+                    Some(_) => perceived.iter().collect(),
+                    None => neightbors,
+                };
+
+                // Projection model: only the boids not hidden behind closer ones are neighbors,
+                // and the projection steering replaces cohesion and alignment
+                let mut projection_dv = Vec2::ZERO;
+                let mut opacity = None;
+                let neightbors: Vec<&Boid> = match config.interaction {
+                    InteractionConfig::Metric => neightbors,
+                    InteractionConfig::Projection {
+                        body_radius,
+                        projection_factor,
+                        alignment_factor,
+                        ..
+                    } => {
+                        let seen = projection::project(current_boid.pos, &neightbors, body_radius);
+                        let visible: Vec<&Boid> = neightbors
+                            .iter()
+                            .zip(&seen.visible)
+                            .filter(|(_, visible)| **visible)
+                            .map(|(neighbor, _)| *neighbor)
+                            .collect();
+                        projection_dv = projection::velocity_change(
+                            current_boid,
+                            &seen,
+                            &visible,
+                            projection_factor,
+                            alignment_factor,
+                        );
+                        opacity = Some(seen.opacity);
+                        genes.matchingfactor = 0.0;
+                        genes.centeringfactor = 0.0;
+                        visible
+                    }
+                };
+
                 // Calculate velocity change for this boid
-                current_boid.calculate_velocity_change(&neightbors, config, screen_dims)
+                let mut delta_v = current_boid.calculate_velocity_change(
+                    &neightbors,
+                    config,
+                    &genes,
+                    screen_dims,
+                ) + projection_dv;
+
+                // Boids in formation hold their slot instead of flocking
+                if let (Some(Some((target, leader_vel))), Some(params)) =
+                    (slots.get(i), &config.formation)
+                {
+                    delta_v =
+                        formation::slot_velocity_change(current_boid, *target, *leader_vel, params);
+                }
+
+                // Morphing boids fly to their target point
+                if let (Some(morph), Some(target)) = (morph, target) {
+                    delta_v += morph.velocity_change(current_boid, target, config.maxspeed);
+                }
+
+                // Escaping boids drop the flocking rules for a fast escape turn
+                if let Some(escape) = escape {
+                    delta_v = escape * config.maxspeed - current_boid.vel;
+                }
+
+                // Calm boids watch for alarmed neighbors
+                let exposure = match config.startle {
+                    Some(_) if current_boid.alarm.is_calm() => {
+                        startle::exposure(neightbors.iter().map(|neighbor| &neighbor.alarm))
+                    }
+                    _ => None,
+                };
+
+                // Switch state when a transition's conditions hold, flee the closest predator
+                let predator =
+                    machine.and_then(|_| behavior::closest_predator(current_boid.pos, obstacles));
+                let next_state = machine.and_then(|machine| {
+                    let senses = Senses {
+                        neighbors: neightbors.len(),
+                        predator_distance: predator.map(|(distance, _)| distance),
+                    };
+                    machine.next_state(current_boid, &senses)
+                });
+                if let (Some(weights), Some((_, away))) = (&weights, predator) {
+                    delta_v += away * weights.flee;
+                }
+
+                // Steer around the obstacles
+                delta_v += obstacles::avoidance_velocity_change(
+                    current_boid,
+                    obstacles,
+                    world_map,
+                    &config.obstacle_avoidance,
+                    config.dt,
+                );
+
+                // Steer away from the map walls and towards the goals
+                if let Some(map) = world_map {
+                    delta_v += map.steering_velocity_change(current_boid);
+                }
+
+                // Steer towards the food sources in sensing range
+                if let Some(foraging) = &config.foraging {
+                    let weight = weights.map_or(1.0, |weights| weights.foraging);
+                    delta_v += food::foraging_velocity_change(current_boid, food_sources, foraging)
+                        * weight;
+                }
+
+                // Follow the pheromone gradient
+                if let (Some(grid), Some(params)) = (pheromone, &config.pheromone) {
+                    delta_v += grid.velocity_change(current_boid, params);
+                }
+
+                // Head for the roost
+                if let Some(roost) = home
+                    && homing > 0.0
+                {
+                    delta_v += roost.homing_velocity_change(current_boid, homing);
+                }
+
+                // Landed boids settle on their roost
+                let settle = match (current_boid.roost.is_landed(), &config.roosting) {
+                    (true, Some(params)) => Some(-current_boid.vel * params.settle_factor),
+                    _ => None,
+                };
+                // With ORCA this is the new velocity itself, otherwise the change to apply
+                let delta_v = match config.avoidance {
+                    AvoidanceConfig::Separation => settle.unwrap_or(delta_v),
+                    AvoidanceConfig::Orca {
+                        radius,
+                        time_horizon,
+                    } => {
+                        // Replace the flocking (or settling) velocity by the closest collision-free one
+                        let preferred = (current_boid.vel + settle.unwrap_or(delta_v))
+                            .clamp_length_max(config.maxspeed);
+                        orca::collision_free_velocity(
+                            current_boid,
+                            preferred,
+                            &neightbors,
+                            radius,
+                            time_horizon,
+                            config.dt,
+                            config.maxspeed,
+                        )
+                    }
+                    AvoidanceConfig::TimeToCollision {
+                        radius,
+                        horizon,
+                        strength,
+                    } => settle.unwrap_or_else(|| {
+                        // Add the anticipatory avoidance to the flocking rules
+                        delta_v
+                            + ttc::avoidance_velocity_change(
+                                current_boid,
+                                &neightbors,
+                                radius,
+                                horizon,
+                                strength,
+                                config.dt,
+                            )
+                    }),
+                };
+                (delta_v, local_config, exposure, next_state, opacity)
             })
-            .collect(); // Collect the calculated Vec2 changes into a new vector
+            .collect(); // Collect the calculated changes into a new vector
 
         // --- Parallel Application of Updates ---
         // Use par_iter_mut to modify boids in parallel.
         // Zip the mutable boid iterator with teh calculated velocity change
+        let orca = matches!(self.config.avoidance, AvoidanceConfig::Orca { .. });
         self.boids
             .par_iter_mut() // Mutable parallel iterator over boids
             .zip(velocity_changes.par_iter()) // Zip with parallel iterator over velocity changes
-            .for_each(|(boid, &delta_v)| {
+            .for_each(|(boid, (delta_v, local_config, _, next_state, _))| {
                 // Process each (boid, delta_v) pair in parallel
-                boid.apply_update(delta_v, config);
+                if orca {
+                    boid.apply_velocity(*delta_v, local_config); // Keep the collision-free velocity
+                } else {
+                    boid.apply_update(*delta_v, local_config);
+                }
+                match next_state {
+                    Some(state) => {
+                        boid.behavior = *state;
+                        boid.behavior_steps = 0;
+                    }
+                    None => boid.behavior_steps += 1,
+                }
+            });
+
+        // --- Collisions ---
+        let overlaps = match self.config.collision {
+            Some(collision) => self.resolve_collisions(&collision),
+            None => 0,
+        };
+
+        // --- Obstacles ---
+        // Boids must never end up inside an obstacle or a wall of the map
+        if !self.obstacles.is_empty() || self.world_map.is_some() {
+            let obstacles = &self.obstacles;
+            let world_map = self.world_map.as_ref();
+            self.boids.par_iter_mut().for_each(|boid| {
+                for obstacle in obstacles {
+                    obstacle.push_out(boid);
+                }
+                if let Some(map) = world_map {
+                    map.push_out(boid);
+                }
+            });
+        }
+
+        // --- Roosting ---
+        // Homing boids reaching their roost at night land on it
+        if night {
+            let roosts = &self.roosts;
+            self.boids.par_iter_mut().for_each(|boid| {
+                if let RoostState::Homing { roost } = boid.roost
+                    && roosts[roost].contains(boid.pos)
+                {
+                    boid.roost = RoostState::Landed { roost };
+                }
+            });
+        }
+
+        // --- Startle ---
+        if let Some(startle) = self.config.startle {
+            let exposures: Vec<Option<Exposure>> = velocity_changes.iter().map(|c| c.2).collect();
+            self.update_startle(&startle, &exposures);
+        }
+
+        // --- Pheromone ---
+        // Boids mark their cells, then the field spreads and fades
+        if let (Some(grid), Some(params)) = (self.pheromone.as_mut(), self.config.pheromone) {
+            grid.deposit(&self.boids, params.deposit);
+            grid.diffuse(params.diffusion, params.evaporation);
+        }
+
+        // --- Food ---
+        // Share the food between the boids feeding on each source
+        let foraging = self.config.foraging.is_some();
+        let feeding = self.config.life.is_some() || self.config.evolution.is_some() || foraging;
+        if feeding {
+            let boids = &self.boids;
+            for source in self.food.iter_mut() {
+                let feeders = boids.par_iter().filter(|b| source.contains(b.pos)).count();
+                source.serve(feeders);
+            }
+        }
+        if self.config.life.is_none() && feeding {
+            // Energy is otherwise gathered by the life cycle, gather it here for the statistics
+            let food = &self.food;
+            self.boids.par_iter_mut().for_each(|boid| {
+                boid.gather_food(food);
+            });
+        }
+
+        // --- Life Cycle ---
+        let (births, deaths) = match self.config.life {
+            Some(life) => self.update_life_cycle(&life),
+            None => (0, 0),
+        };
+
+        // --- Through-Flow ---
+        // Boids entering a sink leave the simulation, the emitters spawn new ones
+        let absorbed = if self.sinks.is_empty() {
+            0
+        } else {
+            let sinks = self.sinks.clone();
+            self.remove_boids(|boid| sinks.iter().any(|sink| sink.contains(boid.pos)))
+        };
+        let emitted = self.update_emitters();
+
+        // --- Evolution ---
+        let mut caught = 0;
+        if let Some(evolution) = self.config.evolution {
+            // Predators kill the boids they catch, which keep their fitness until the
+            // generation ends
+            if let Some(catch_distance) = evolution.catch_distance {
+                let obstacles = &self.obstacles;
+                let (fallen, alive): (Vec<Boid>, Vec<Boid>) = std::mem::take(&mut self.boids)
+                    .into_iter()
+                    .partition(|boid| {
+                        behavior::closest_predator(boid.pos, obstacles)
+                            .is_some_and(|(distance, _)| distance < catch_distance)
+                    });
+                self.boids = alive;
+                caught = fallen.len();
+                if caught > 0 {
+                    self.fallen.extend(fallen);
+                    self.build_kdtree();
+                }
+            }
+            if evolution.predator_weight != 0.0 {
+                // A step counts fully when the closest predator is beyond visible_range
+                let (obstacles, range) = (&self.obstacles, self.config.visible_range.max(1e-6));
+                self.boids.par_iter_mut().for_each(|boid| {
+                    if let Some((distance, _)) = behavior::closest_predator(boid.pos, obstacles) {
+                        boid.predator_clearance += (distance / range).min(1.0);
+                    }
+                });
+            }
+            if self.step.is_multiple_of(evolution.generation_steps.max(1)) {
+                self.end_generation(&evolution);
+            }
+        }
+
+        let (group_count, group_spread, group_distance) = match self.config.groups {
+            Some(_) => groups::group_stats(&self.boids),
+            None => (0, 0.0, 0.0),
+        };
+        self.record_stats(StepStats {
+            births,
+            deaths: deaths + caught,
+            overlaps,
+            polarization: self.polarization(),
+            opacity: mean(velocity_changes.iter().filter_map(|c| c.4)),
+            alarmed: self
+                .boids
+                .iter()
+                .filter(|boid| boid.alarm.escape().is_some())
+                .count(),
+            formation_error,
+            morph_error,
+            roosting: self
+                .boids
+                .iter()
+                .filter(|boid| boid.roost.is_landed())
+                .count(),
+            states: self.state_counts(),
+            groups: group_count,
+            group_spread,
+            group_distance,
+            emitted,
+            absorbed,
+            ..StepStats::default()
+        });
+    }
+
+    /// Applies a scripted event. Events that cannot be applied (unknown parameter,
+    /// missing attractor, unreadable image, unwritable snapshot) are reported and skipped.
+    ///
+    /// # Arguments
+    ///
+    /// * `action` - The event.
+    fn apply_event(&mut self, action: TimelineAction) {
+        match action {
+            TimelineAction::Spawn {
+                count,
+                pattern,
+                velocity,
+            } => {
+                let mut rng = StdRng::from_rng(&mut self.rng);
+                let config = SpawnConfig { pattern, velocity };
+                match spawn::spawn_states(&config, count, self.screen_dims, &mut rng) {
+                    Ok(states) => {
+                        for (pos, vel) in states {
+                            self.add_boid_with_velocity(pos, vel, &mut rng);
+                        }
+                    }
+                    Err(e) => eprintln!("Step {}: error spawning boids: {}", self.step, e),
+                }
+            }
+            TimelineAction::Predator { obstacle } => {
+                self.add_obstacle(Obstacle::new(&obstacle));
+            }
+            TimelineAction::MoveAttractor {
+                attractor,
+                index,
+                x,
+                y,
+            } => {
+                let pos = match attractor {
+                    Attractor::Food => self.food.get_mut(index).map(|source| &mut source.pos),
+                    Attractor::Roost => self.roosts.get_mut(index).map(|roost| &mut roost.pos),
+                };
+                match pos {
+                    Some(pos) => *pos = Vec2::new(x, y),
+                    None => eprintln!(
+                        "Step {}: no {} attractor {} to move",
+                        self.step,
+                        attractor.name(),
+                        index
+                    ),
+                }
+            }
+            TimelineAction::SetParam { name, value } => {
+                if let Err(e) = self.set_param(&name, value) {
+                    eprintln!("Step {}: {}", self.step, e);
+                }
+            }
+            TimelineAction::ToggleRule { rule, enabled } => {
+                let enabled = enabled.unwrap_or(self.disabled_rules[rule.index()]);
+                self.set_rule(rule, enabled);
+            }
+            TimelineAction::Pause => self.pause_requested = true,
+            TimelineAction::Snapshot { path } => {
+                let path = path.unwrap_or_else(|| format!("snapshot_{}.csv", self.step));
+                match std::fs::write(&path, scenario::to_csv(&self.boids)) {
+                    Ok(()) => println!("Step {}: snapshot written to '{}'", self.step, path),
+                    Err(e) => eprintln!("Step {}: error writing '{}': {}", self.step, path, e),
+                }
+            }
+        }
+    }
+
+    /// Lets every emitter spawn its boids for this step.
+    ///
+    /// # Returns
+    ///
+    /// * `usize` - The number of boids spawned.
+    fn update_emitters(&mut self) -> usize {
+        if self.emitters.is_empty() {
+            return 0;
+        }
+        let mut rng = StdRng::from_rng(&mut self.rng);
+        let mut emitted = 0;
+        for e in 0..self.emitters.len() {
+            let count = self.emitters[e].emit(self.boids.len());
+            for _ in 0..count {
+                let (pos, vel) = self.emitters[e].spawn_state(&mut rng);
+                let genes = Genes::from_config(&self.config);
+                let mut boid = self.spawn_boid(pos, genes, &mut rng);
+                boid.vel = vel;
+                self.boids.push(boid);
+            }
+            emitted += count;
+        }
+        emitted
+    }
+
+    /// Counts the boids in every behavioural state (all zero without a state machine).
+    fn state_counts(&self) -> [usize; BehaviorState::COUNT] {
+        let mut counts = [0; BehaviorState::COUNT];
+        if self.behavior.is_some() {
+            for boid in &self.boids {
+                counts[boid.behavior.index()] += 1;
+            }
+        }
+        counts
+    }
+
+    /// Advances the day cycle: at dawn every boid takes off and forgets its roost,
+    /// and once homing has started each flock without a roost is given the one
+    /// closest to its center.
+    ///
+    /// # Arguments
+    ///
+    /// * `params` - The RoostingConfig parameters.
+    ///
+    /// # Returns
+    ///
+    /// * `(f32, bool)` - The current homing strength, and whether it is night (boids
+    ///   may land).
+    fn update_roosting(&mut self, params: &RoostingConfig) -> (f32, bool) {
+        let phase = roost::day_phase(self.step, params);
+        if self.step.is_multiple_of(params.day_length.max(1)) {
+            let maxspeed = self.config.maxspeed;
+            for boid in self.boids.iter_mut() {
+                if boid.roost.is_landed() {
+                    let angle = self.rng.random::<f32>() * 2.0 * std::f32::consts::PI;
+                    boid.vel = Vec2::from_angle(angle) * maxspeed;
+                }
+                boid.roost = RoostState::Free;
+            }
+        }
+
+        let homing = roost::homing_strength(phase, params);
+        if homing > 0.0 && self.boids.iter().any(|boid| boid.roost.roost().is_none()) {
+            // Center of every flock
+            let labels = self.flock_labels();
+            let mut centers: HashMap<usize, (Vec2, usize)> = HashMap::new();
+            for (boid, label) in self.boids.iter().zip(&labels) {
+                let center = centers.entry(*label).or_insert((Vec2::ZERO, 0));
+                center.0 += boid.pos;
+                center.1 += 1;
+            }
+            for (boid, label) in self.boids.iter_mut().zip(&labels) {
+                if boid.roost.roost().is_some() {
+                    continue;
+                }
+                let (sum, count) = centers[label];
+                let center = sum / count as f32;
+                let closest = (0..self.roosts.len()).min_by(|&a, &b| {
+                    let da = self.roosts[a].pos.distance_squared(center);
+                    let db = self.roosts[b].pos.distance_squared(center);
+                    da.total_cmp(&db)
+                });
+                if let Some(roost) = closest {
+                    boid.roost = RoostState::Homing { roost };
+                }
+            }
+        }
+        (homing, phase >= params.dusk)
+    }
+
+    /// Maintains the family groups for one step:
+    /// 1. At the first step, splits the boids into groups of spawn_size neighbors.
+    /// 2. Removes the members further than leave_distance from their group center.
+    /// 3. Lets every boid without a group join the group of its closest neighbor
+    ///    within join_distance, or found a new group with it if it has none.
+    ///
+    /// # Arguments
+    ///
+    /// * `params` - The GroupsConfig parameters.
+    fn update_groups(&mut self, params: &GroupsConfig) {
+        if self.step == 1 && params.spawn_size > 0 && self.boids.iter().all(|b| b.group.is_none()) {
+            self.form_groups(params.spawn_size);
+        }
+
+        let mut centers = groups::group_centers(&self.boids);
+        if let Some(leave_distance) = params.leave_distance {
+            for boid in self.boids.iter_mut() {
+                if let Some(group) = boid.group
+                    && boid.pos.distance(centers[&group].0) > leave_distance
+                {
+                    boid.group = None;
+                    if let Some(center) = centers.get_mut(&group) {
+                        center.1 -= 1;
+                    }
+                }
+            }
+        }
+
+        let Some(join_distance) = params.join_distance else {
+            return;
+        };
+        let max_size = params.max_size.unwrap_or(usize::MAX);
+        for i in 0..self.boids.len() {
+            if self.boids[i].group.is_some() {
+                continue;
+            }
+            let pos = [self.boids[i].pos.x, self.boids[i].pos.y];
+            let closest = self
+                .kdtree
+                .nearest(&pos, 2, &squared_euclidean)
+                .unwrap_or_default()
+                .into_iter()
+                .find(|&(dist_sq, &j)| j != i && dist_sq <= join_distance * join_distance);
+            let Some((_, &j)) = closest else {
+                continue;
+            };
+            match self.boids[j].group {
+                Some(group) if centers.get(&group).is_some_and(|c| c.1 < max_size) => {
+                    self.boids[i].group = Some(group);
+                    if let Some(center) = centers.get_mut(&group) {
+                        center.1 += 1;
+                    }
+                }
+                None if max_size >= 2 => {
+                    let group = self.next_group;
+                    self.next_group += 1;
+                    self.boids[i].group = Some(group);
+                    self.boids[j].group = Some(group);
+                    centers.insert(group, (self.boids[i].pos, 2));
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// Splits the boids into groups: each boid not yet in a group founds one,
+    /// joined by its closest free boids up to the group size.
+    ///
+    /// # Arguments
+    ///
+    /// * `size` - The number of boids per group.
+    fn form_groups(&mut self, size: usize) {
+        for i in 0..self.boids.len() {
+            if self.boids[i].group.is_some() {
+                continue;
+            }
+            let group = self.next_group;
+            self.next_group += 1;
+            self.boids[i].group = Some(group);
+            let pos = [self.boids[i].pos.x, self.boids[i].pos.y];
+            // Look a bit beyond the size, the closest boids may be taken already
+            let closest = self
+                .kdtree
+                .nearest(&pos, size * 3, &squared_euclidean)
+                .unwrap_or_default();
+            let mut members = 1;
+            for (_dist_sq, &j) in closest {
+                if members == size {
+                    break;
+                }
+                if self.boids[j].group.is_none() {
+                    self.boids[j].group = Some(group);
+                    members += 1;
+                }
+            }
+        }
+    }
+
+    /// Groups the free boids into formations: each boid not yet in a formation leads
+    /// one, joined by its closest free boids up to the formation size.
+    ///
+    /// # Arguments
+    ///
+    /// * `params` - The FormationConfig parameters.
+    fn form_formations(&mut self, params: &FormationConfig) {
+        let members_per_formation = params.size.max(1) - 1;
+        let in_formation: HashSet<Uuid> = self
+            .formations
+            .iter()
+            .flat_map(|formation| std::iter::once(&formation.leader).chain(&formation.members))
+            .copied()
+            .collect();
+        let mut assigned: Vec<bool> = self
+            .boids
+            .iter()
+            .map(|boid| in_formation.contains(&boid.id))
+            .collect();
+        for i in 0..self.boids.len() {
+            if assigned[i] {
+                continue;
+            }
+            assigned[i] = true;
+            let pos = [self.boids[i].pos.x, self.boids[i].pos.y];
+            // Look a bit beyond the size, the closest boids may be taken already
+            let closest = self
+                .kdtree
+                .nearest(&pos, params.size * 3, &squared_euclidean)
+                .unwrap_or_default();
+            let mut members = Vec::new();
+            for (_dist_sq, &j) in closest {
+                if members.len() == members_per_formation {
+                    break;
+                }
+                if !assigned[j] {
+                    assigned[j] = true;
+                    members.push(self.boids[j].id);
+                }
+            }
+            self.formations.push(Formation {
+                leader: self.boids[i].id,
+                members,
+            });
+        }
+    }
+
+    /// Maintains the formations for one step and returns the slot of every boid:
+    /// 0. Forms new formations from the free boids when boids were added (emitters,
+    ///    offspring, spawn events or a new generation).
+    /// 1. Removes the boids that died, and the members further than the break
+    ///    distance from their slot, who fall back to flocking.
+    /// 2. Promotes a member when the leader is gone.
+    /// 3. Lets free boids close to the next open slot of a formation join it.
+    /// 4. Reassigns the slots of the formations whose members changed.
+    ///
+    /// # Arguments
+    ///
+    /// * `params` - The FormationConfig parameters.
+    ///
+    /// # Returns
+    ///
+    /// * `(Vec<Option<(Vec2, Vec2)>>, f32)` - For each boid, its slot target and leader
+    ///   velocity if it flies in formation; and the mean distance of the members from
+    ///   their slots.
+    fn update_formations(&mut self, params: &FormationConfig) -> (Vec<Option<(Vec2, Vec2)>>, f32) {
+        if self
+            .boids
+            .iter()
+            .any(|boid| !self.formed.contains(&boid.id))
+        {
+            self.form_formations(params);
+            self.formed = self.boids.iter().map(|boid| boid.id).collect();
+        }
+        let index_of: HashMap<Uuid, usize> = self
+            .boids
+            .iter()
+            .enumerate()
+            .map(|(i, boid)| (boid.id, i))
+            .collect();
+
+        // --- Departures ---
+        let mut formations = Vec::new();
+        let mut changed = Vec::new();
+        for mut formation in std::mem::take(&mut self.formations) {
+            let before = formation.members.len();
+            formation.members.retain(|id| index_of.contains_key(id));
+            if !index_of.contains_key(&formation.leader) {
+                if formation.members.is_empty() {
+                    continue; // Nobody left
+                }
+                formation.leader = formation.members.remove(0);
+            }
+            let leader = &self.boids[index_of[&formation.leader]];
+            let targets = Formation::slot_targets(leader, params, formation.members.len());
+            let mut slot = 0;
+            formation.members.retain(|id| {
+                let dist = self.boids[index_of[id]].pos.distance(targets[slot]);
+                slot += 1;
+                dist <= params.break_distance
+            });
+            changed.push(formation.members.len() != before);
+            formations.push(formation);
+        }
+        self.formations = formations;
+
+        // --- Arrivals ---
+        let mut in_formation = vec![false; self.boids.len()];
+        for formation in &self.formations {
+            in_formation[index_of[&formation.leader]] = true;
+            for id in &formation.members {
+                in_formation[index_of[id]] = true;
+            }
+        }
+        let join_distance_sq = (params.break_distance * 0.5).powi(2);
+        for (formation, changed) in self.formations.iter_mut().zip(changed.iter_mut()) {
+            let open = formation.members.len();
+            if open + 1 >= params.size {
+                continue; // Full
+            }
+            let leader = &self.boids[index_of[&formation.leader]];
+            let target = Formation::slot_targets(leader, params, open + 1)[open];
+            let candidates = self
+                .kdtree
+                .within(&[target.x, target.y], join_distance_sq, &squared_euclidean)
+                .unwrap_or_default();
+            if let Some(&(_dist_sq, &j)) = candidates.iter().find(|&&(_, &j)| !in_formation[j]) {
+                in_formation[j] = true;
+                formation.members.push(self.boids[j].id);
+                *changed = true;
+            }
+        }
+
+        // --- Slots ---
+        let mut slots = vec![None; self.boids.len()];
+        let (mut error_sum, mut members) = (0.0, 0);
+        for (formation, changed) in self.formations.iter_mut().zip(changed) {
+            let leader = &self.boids[index_of[&formation.leader]];
+            let targets = Formation::slot_targets(leader, params, formation.members.len());
+            if changed {
+                formation.assign_slots(&self.boids, &index_of, &targets);
+            }
+            for (id, target) in formation.members.iter().zip(&targets) {
+                let i = index_of[id];
+                slots[i] = Some((*target, leader.vel));
+                error_sum += self.boids[i].pos.distance(*target);
+                members += 1;
+            }
+        }
+        let error = if members > 0 {
+            error_sum / members as f32
+        } else {
+            0.0
+        };
+        (slots, error)
+    }
+
+    /// Advances the startle state of every boid for one step:
+    /// 1. Counts down the pending, alarmed and refractory periods.
+    /// 2. Spreads the alarm to the calm boids exposed to alarmed neighbors.
+    /// 3. Starts new cascades from random alarms and approaching predators
+    ///    (moving obstacles).
+    /// 4. Moves the cascades without pending or alarmed boids to the finished ones.
+    ///
+    /// # Arguments
+    ///
+    /// * `startle` - The StartleConfig parameters.
+    /// * `exposures` - The exposure of every boid to alarmed neighbors.
+    fn update_startle(&mut self, startle: &StartleConfig, exposures: &[Option<Exposure>]) {
+        let step = self.step;
+
+        // --- Timers ---
+        for boid in self.boids.iter_mut() {
+            boid.alarm = match boid.alarm {
+                AlarmState::Pending {
+                    steps_left: 0,
+                    cascade,
+                    escape,
+                } => match self.cascades.get_mut(&cascade) {
+                    Some(cascade) => {
+                        alarm_boid(boid, cascade, escape, startle, step);
+                        boid.alarm
+                    }
+                    None => AlarmState::Calm,
+                },
+                AlarmState::Pending {
+                    steps_left,
+                    cascade,
+                    escape,
+                } => AlarmState::Pending {
+                    steps_left: steps_left - 1,
+                    cascade,
+                    escape,
+                },
+                AlarmState::Alarmed { steps_left: 0, .. } => AlarmState::Refractory {
+                    steps_left: startle.refractory,
+                },
+                AlarmState::Alarmed {
+                    steps_left,
+                    cascade,
+                    escape,
+                } => AlarmState::Alarmed {
+                    steps_left: steps_left - 1,
+                    cascade,
+                    escape,
+                },
+                AlarmState::Refractory { steps_left: 0 } | AlarmState::Calm => AlarmState::Calm,
+                AlarmState::Refractory { steps_left } => AlarmState::Refractory {
+                    steps_left: steps_left - 1,
+                },
+            };
+        }
+
+        // --- Propagation ---
+        for (boid, exposure) in self.boids.iter_mut().zip(exposures) {
+            let Some(exposure) = exposure else {
+                continue;
+            };
+            if !boid.alarm.is_calm() {
+                continue;
+            }
+            // Each alarmed neighbor passes the alarm independently
+            let p_calm = (1.0 - startle.probability).powi(exposure.alarmed_neighbors as i32);
+            if self.rng.random::<f32>() >= 1.0 - p_calm {
+                continue;
+            }
+            if startle.delay == 0 {
+                if let Some(cascade) = self.cascades.get_mut(&exposure.cascade) {
+                    alarm_boid(boid, cascade, exposure.escape, startle, step);
+                }
+            } else {
+                boid.alarm = AlarmState::Pending {
+                    steps_left: startle.delay - 1,
+                    cascade: exposure.cascade,
+                    escape: exposure.escape,
+                };
+            }
+        }
+
+        // --- Random Alarms ---
+        if startle.random_rate > 0.0 {
+            for i in 0..self.boids.len() {
+                if self.boids[i].alarm.is_calm() && self.rng.random::<f32>() < startle.random_rate {
+                    self.start_cascade(i, StartleTrigger::Random, None, startle);
+                }
+            }
+        }
+
+        // --- Predators ---
+        // Every moving obstacle starts one cascade with the boids it comes close to
+        if let Some(distance) = startle.predator_distance {
+            for o in 0..self.obstacles.len() {
+                if self.obstacles[o].vel.length_squared() < 1e-12 {
+                    continue; // Static obstacles are not predators
+                }
+                let shape = self.obstacles[o].shape;
+                let mut cascade = None;
+                for i in 0..self.boids.len() {
+                    let boid = &self.boids[i];
+                    if !boid.alarm.is_calm() || shape.signed_distance(boid.pos) > distance {
+                        continue;
+                    }
+                    let away = (boid.pos - shape.center())
+                        .try_normalize()
+                        .unwrap_or(Vec2::X);
+                    match cascade.and_then(|c| self.cascades.get_mut(&c)) {
+                        Some(cascade) => {
+                            alarm_boid(&mut self.boids[i], cascade, away, startle, step)
+                        }
+                        None => {
+                            cascade = Some(self.start_cascade(
+                                i,
+                                StartleTrigger::Predator,
+                                Some(away),
+                                startle,
+                            ));
+                        }
+                    }
+                }
+            }
+        }
+
+        // --- Finished Cascades ---
+        // Only the open cascades are kept, the finished ones wait to be logged
+        let active: HashSet<usize> = self
+            .boids
+            .iter()
+            .filter_map(|boid| boid.alarm.cascade())
+            .collect();
+        let finished: Vec<usize> = self
+            .cascades
+            .keys()
+            .filter(|id| !active.contains(id))
+            .copied()
+            .collect();
+        for id in finished {
+            if let Some(mut cascade) = self.cascades.remove(&id) {
+                cascade.finished = true;
+                self.finished_cascades.push(cascade);
+            }
+        }
+    }
+
+    /// Takes the startle cascades finished since the last call.
+    ///
+    /// # Returns
+    ///
+    /// * `Vec<CascadeStats>` - The finished cascades, by id.
+    pub fn take_finished_cascades(&mut self) -> Vec<CascadeStats> {
+        let mut finished = std::mem::take(&mut self.finished_cascades);
+        finished.sort_by_key(|cascade| cascade.id);
+        finished
+    }
+
+    /// Starts a new startle cascade by alarming a boid.
+    ///
+    /// # Arguments
+    ///
+    /// * `index` - The index of the first alarmed boid.
+    /// * `trigger` - What started the cascade.
+    /// * `escape` - The escape direction, or None for a turn of turn_angle to a random side.
+    /// * `startle` - The StartleConfig parameters.
+    ///
+    /// # Returns
+    ///
+    /// * `usize` - The id of the new cascade.
+    fn start_cascade(
+        &mut self,
+        index: usize,
+        trigger: StartleTrigger,
+        escape: Option<Vec2>,
+        startle: &StartleConfig,
+    ) -> usize {
+        let boid = &mut self.boids[index];
+        let escape = escape
+            .unwrap_or_else(|| startle::escape_turn(boid.vel, startle.turn_angle, &mut self.rng));
+        let id = self.next_cascade;
+        self.next_cascade += 1;
+        let cascade = self.cascades.entry(id).or_insert(CascadeStats {
+            id,
+            trigger,
+            start_step: self.step,
+            last_step: self.step,
+            origin: boid.pos,
+            reach: 0,
+            max_distance: 0.0,
+            finished: false,
+        });
+        alarm_boid(boid, cascade, escape, startle, self.step);
+        id
+    }
+
+    /// Alarms a random boid, starting a new cascade (startle must be enabled).
+    pub fn startle_random_boid(&mut self) {
+        if let Some(startle) = self.config.startle
+            && !self.boids.is_empty()
+        {
+            let index = self.rng.random_range(0..self.boids.len());
+            self.start_cascade(index, StartleTrigger::Event, None, &startle);
+        }
+    }
+
+    /// Returns the polarization (order parameter) of the population: the length of the
+    /// average heading, 1.0 when all boids fly the same direction, near 0.0 when disordered.
+    fn polarization(&self) -> f32 {
+        if self.boids.is_empty() {
+            return 0.0;
+        }
+        let sum: Vec2 = self
+            .boids
+            .iter()
+            .map(|boid| boid.vel.normalize_or_zero())
+            .sum();
+        sum.length() / self.boids.len() as f32
+    }
+
+    /// Counts the flocks: groups of boids connected through chains of neighbors
+    /// within the visible range. Used to measure how a group splits and reforms.
+    ///
+    /// # Returns
+    ///
+    /// * `usize` - The number of flocks (isolated boids count as one flock).
+    pub fn count_flocks(&mut self) -> usize {
+        let labels = self.flock_labels();
+        labels
+            .iter()
+            .enumerate()
+            .filter(|&(i, &label)| label == i)
+            .count()
+    }
+
+    /// Labels every boid with its flock (see count_flocks).
+    ///
+    /// # Returns
+    ///
+    /// * `Vec<usize>` - For each boid, the index of a boid representing its flock.
+    fn flock_labels(&mut self) -> Vec<usize> {
+        self.build_kdtree();
+        let visible_range_sq = self.config.visible_range * self.config.visible_range;
+
+        // Union-find over the neighbor graph
+        let mut parent: Vec<usize> = (0..self.boids.len()).collect();
+        fn find(parent: &mut [usize], mut i: usize) -> usize {
+            while parent[i] != i {
+                parent[i] = parent[parent[i]]; // Path halving
+                i = parent[i];
+            }
+            i
+        }
+        for (i, boid) in self.boids.iter().enumerate() {
+            let neighbors = self
+                .kdtree
+                .within(
+                    &[boid.pos.x, boid.pos.y],
+                    visible_range_sq,
+                    &squared_euclidean,
+                )
+                .unwrap_or_default();
+            for (_dist_sq, &j) in neighbors {
+                let (root_i, root_j) = (find(&mut parent, i), find(&mut parent, j));
+                if root_i != root_j {
+                    parent[root_i] = root_j;
+                }
+            }
+        }
+        (0..self.boids.len())
+            .map(|i| find(&mut parent, i))
+            .collect()
+    }
+
+    /// Pushes apart boids closer than twice the body radius by projecting their positions.
+    /// Each pass moves both boids of an overlapping pair by half of the overlap, using the
+    /// KDTree rebuilt on the current positions.
+    ///
+    /// # Arguments
+    ///
+    /// * `collision` - The CollisionConfig parameters.
+    ///
+    /// # Returns
+    ///
+    /// * `usize` - The number of overlapping pairs found before the first pass.
+    fn resolve_collisions(&mut self, collision: &CollisionConfig) -> usize {
+        let min_dist = 2.0 * collision.body_radius;
+        let min_dist_sq = min_dist * min_dist;
+        let mut overlaps = 0;
+
+        for pass in 0..collision.iterations.max(1) {
+            self.build_kdtree();
+            let kdtree = &self.kdtree;
+            let boids_ref = &self.boids;
+
+            // Compute every correction from the same positions (Jacobi style) in parallel
+            let corrections: Vec<(Vec2, usize)> = boids_ref
+                .par_iter()
+                .enumerate()
+                .map(|(i, boid)| {
+                    let mut correction = Vec2::ZERO;
+                    let mut count = 0;
+                    let others = kdtree
+                        .within(&[boid.pos.x, boid.pos.y], min_dist_sq, &squared_euclidean)
+                        .unwrap_or_default();
+                    for (dist_sq, &j) in others {
+                        if j == i {
+                            continue;
+                        }
+                        let dist = dist_sq.sqrt();
+                        // Boids on top of each other are separated along x, by index order
+                        let dir = if dist > 1e-6 {
+                            (boid.pos - boids_ref[j].pos) / dist
+                        } else if i < j {
+                            Vec2::X
+                        } else {
+                            -Vec2::X
+                        };
+                        correction += dir * (min_dist - dist) * 0.5;
+                        count += 1;
+                    }
+                    (correction, count)
+                })
+                .collect();
+
+            let pass_overlaps = corrections.iter().map(|&(_, count)| count).sum::<usize>() / 2;
+            if pass == 0 {
+                overlaps = pass_overlaps;
+            }
+            if pass_overlaps == 0 {
+                break;
+            }
+            self.boids
+                .par_iter_mut()
+                .zip(corrections.par_iter())
+                .for_each(|(boid, &(correction, _))| boid.pos += correction);
+        }
+        overlaps
+    }
+
+    /// Logs the gene distribution of the current generation (boids caught by the
+    /// predators included) and, in generational mode, replaces the population with as
+    /// many mutated offspring of tournament-selected survivors.
+    ///
+    /// # Arguments
+    ///
+    /// * `evolution` - The EvolutionConfig parameters.
+    fn end_generation(&mut self, evolution: &EvolutionConfig) {
+        // The boids caught by the predators count in the generation, with the fitness
+        // they had when caught
+        let fallen = std::mem::take(&mut self.fallen);
+        let generation_boids: Vec<&Boid> = self.boids.iter().chain(&fallen).collect();
+        let genes: Vec<Genes> = generation_boids.iter().map(|boid| boid.genes).collect();
+        let fitness: Vec<f32> = generation_boids
+            .iter()
+            .map(|boid| boid.fitness(evolution))
+            .collect();
+        let generation = self.generations.len() as u64;
+        self.generations.push(GenerationStats::new(
+            generation, self.step, &genes, &fitness,
+        ));
+
+        if !evolution.generational || generation_boids.is_empty() {
+            return;
+        }
+        // The survivors are the parents (the fallen only if nobody survived), the
+        // offspring restore the population of the generation
+        let parents = if self.boids.is_empty() {
+            generation_boids.len()
+        } else {
+            self.boids.len()
+        };
+        let mut rng = StdRng::from_rng(&mut self.rng);
+        let spread = evolution
+            .offspring_spread
+            .unwrap_or(self.config.protected_range);
+        let next: Vec<Boid> = (0..generation_boids.len())
+            .map(|_| {
+                let parent =
+                    tournament_select(&fitness[..parents], evolution.tournament_size, &mut rng);
+                let child_genes = genes[parent].mutate(evolution.mutation_sigma, &mut rng);
+                // Place the offspring at a random point of a disk around the parent
+                let angle = rng.random::<f32>() * 2.0 * std::f32::consts::PI;
+                let dist = rng.random::<f32>().sqrt() * spread;
+                let parent = generation_boids[parent];
+                let pos = parent.pos + Vec2::new(angle.cos(), angle.sin()) * dist;
+                let mut child = self.spawn_boid(pos, child_genes, &mut rng);
+                child.group = parent.group; // Offspring stay in the family
+                child
             })
+            .collect();
+        self.boids = next;
+        self.build_kdtree();
+    }
+
+    /// Drains and replenishes energy, removes dead boids and lets boids above the
+    /// reproduction threshold spawn an offspring near them.
+    ///
+    /// # Arguments
+    ///
+    /// * `life` - The LifeConfig parameters.
+    ///
+    /// # Returns
+    ///
+    /// * `(usize, usize)` - The number of births and deaths during this step.
+    fn update_life_cycle(&mut self, life: &LifeConfig) -> (usize, usize) {
+        // --- Energy ---
+        let food = &self.food;
+        self.boids
+            .par_iter_mut()
+            .for_each(|boid| boid.update_energy(life, food));
+
+        // --- Death ---
+        let deaths = self.remove_boids(|boid| !boid.is_alive());
+
+        // --- Reproduction ---
+        let population = self.boids.len();
+        let mut rng = StdRng::from_rng(&mut self.rng);
+        let mut offspring = Vec::new();
+        for i in 0..population {
+            if population + offspring.len() >= life.max_population {
+                break;
+            }
+            let parent = &self.boids[i];
+            if parent.energy < life.reproduction_threshold {
+                continue;
+            }
+            // Spawn the offspring at a random point of a disk around the parent
+            let angle = rng.random::<f32>() * 2.0 * std::f32::consts::PI;
+            let dist = rng.random::<f32>().sqrt() * life.offspring_spread;
+            let pos = parent.pos + Vec2::new(angle.cos(), angle.sin()) * dist;
+            let genes = match &self.config.evolution {
+                Some(evolution) => parent.genes.mutate(evolution.mutation_sigma, &mut rng),
+                None => parent.genes,
+            };
+            let mut child = self.spawn_boid(pos, genes, &mut rng);
+            child.energy = parent.energy * life.offspring_energy;
+            child.group = parent.group; // Offspring stay in the family
+            self.boids[i].energy -= child.energy;
+            offspring.push(child);
+        }
+        let births = offspring.len();
+        self.boids.extend(offspring);
+
+        (births, deaths)
+    }
+
+    /// Records the statistics of the current step, filling in the step and population,
+    /// and drops the oldest step beyond HISTORY_LEN.
+    fn record_stats(&mut self, stats: StepStats) {
+        if self.history.len() == HISTORY_LEN {
+            self.history.pop_front();
+        }
+        self.history.push_back(StepStats {
+            step: self.step,
+            population: self.boids.len(),
+            ..stats
+        });
+    }
+}
+
+/// Alarms a boid as part of a cascade and records its reach.
+///
+/// # Arguments
+///
+/// * `boid` - The boid to alarm.
+/// * `cascade` - The cascade the alarm belongs to.
+/// * `escape` - The escape direction.
+/// * `startle` - The StartleConfig parameters.
+/// * `step` - The current step.
+fn alarm_boid(
+    boid: &mut Boid,
+    cascade: &mut CascadeStats,
+    escape: Vec2,
+    startle: &StartleConfig,
+    step: u64,
+) {
+    boid.alarm = AlarmState::Alarmed {
+        steps_left: startle.duration,
+        cascade: cascade.id,
+        escape,
+    };
+    cascade.reach += 1;
+    cascade.last_step = step;
+    cascade.max_distance = cascade.max_distance.max(boid.pos.distance(cascade.origin));
+}
+
+/// Returns the mean of some values, 0 if there are none.
+fn mean(values: impl Iterator<Item = f32>) -> f32 {
+    let (sum, count) = values.fold((0.0, 0), |(sum, count), value| (sum + value, count + 1));
+    if count > 0 { sum / count as f32 } else { 0.0 }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Builds the parameters of a simulation, with the given optional sections.
+    fn config(sections: &str) -> BoidsConfig {
+        serde_yaml::from_str(&format!(
+            "protected_range: 2.0
+visible_range: 5.0
+avoidfactor: 0.0
+matchingfactor: 0.0
+centeringfactor: 0.0
+turnfactor: 0.2
+margin: 100.0
+maxspeed: 2.0
+minspeed: 1.0
+dt: 1.0
+scale: false
+trails_len: 0.1
+{}",
+            sections
+        ))
+        .unwrap()
+    }
+
+    fn formation_simulator(size: usize) -> BoidSimulator {
+        let params = format!(
+            "formation: {{ shape: {{ type: line }}, size: {}, spacing: 10.0, spring: 0.05, \
+             damping: 0.1, break_distance: 1000.0 }}",
+            size
+        );
+        BoidSimulator::new(config(&params), (1000.0, 1000.0))
+    }
+
+    fn add_boids(simulator: &mut BoidSimulator, positions: &[(f32, f32)]) {
+        let mut rng = StdRng::seed_from_u64(0);
+        for &(x, y) in positions {
+            simulator.add_boid_with_velocity(Vec2::new(x, y), Vec2::new(1.5, 0.0), &mut rng);
+        }
+    }
+
+    #[test]
+    fn leader_loss_promotes_a_member_and_reassigns_the_slots() {
+        let mut simulator = formation_simulator(4);
+        add_boids(
+            &mut simulator,
+            &[
+                (500.0, 500.0),
+                (500.0, 508.0),
+                (500.0, 490.0),
+                (500.0, 520.0),
+            ],
+        );
+        simulator.update();
+        assert_eq!(simulator.formations.len(), 1);
+        let ids: Vec<Uuid> = simulator.boids.iter().map(|boid| boid.id).collect();
+        assert_eq!(simulator.formations[0].leader, ids[0]);
+        assert_eq!(simulator.formations[0].members, [ids[1], ids[2], ids[3]]);
+
+        // The closest member takes the lead, the others the slots on either side of it
+        simulator.boids.remove(0);
+        simulator.update();
+        assert_eq!(simulator.formations.len(), 1);
+        let formation = simulator.formations[0].clone();
+        assert_eq!(formation.leader, ids[1]);
+        assert_eq!(formation.members, [ids[3], ids[2]]);
+        let params = simulator.config.formation.unwrap();
+        let leader = &simulator.boids[0];
+        let targets = Formation::slot_targets(leader, &params, 2);
+        let (low, high) = (&simulator.boids[1], &simulator.boids[2]); // ids[2] and ids[3]
+        assert!(high.pos.distance(targets[0]) < high.pos.distance(targets[1]));
+        assert!(low.pos.distance(targets[1]) < low.pos.distance(targets[0]));
+    }
+
+    #[test]
+    fn boids_added_later_form_formations() {
+        let mut simulator = formation_simulator(3);
+        add_boids(
+            &mut simulator,
+            &[(100.0, 100.0), (100.0, 110.0), (100.0, 90.0)],
+        );
+        simulator.update();
+        simulator.update();
+        add_boids(&mut simulator, &[(700.0, 700.0), (700.0, 710.0)]);
+        simulator.update();
+        assert_eq!(simulator.formations.len(), 2);
+        let new = &simulator.formations[1];
+        assert_eq!(new.leader, simulator.boids[3].id);
+        assert_eq!(new.members, [simulator.boids[4].id]);
     }
 }
@@ -0,0 +1,189 @@
+// src/spawn.rs
+// Initial conditions: places the starting boids following a spawn pattern (uniform,
+// Gaussian clusters, ring, grid lattice or image mask) and gives them initial
+// velocities (random, aligned, vortex or outward radial).
+
+use ggez::glam::Vec2;
+use rand::Rng;
+use rand_distr::{Distribution, Normal};
+
+use crate::config::{MorphSourceConfig, SpawnConfig, SpawnPatternConfig, SpawnVelocityConfig};
+use crate::morph::TargetShape;
+
+/// Returns the initial position and velocity of every starting boid.
+///
+/// # Arguments
+///
+/// * 'config' - The SpawnConfig read from boids.yaml.
+/// * 'count' - The number of boids.
+/// * 'world_dims' - The width and height of the simulation area.
+/// * 'rng' - A mutable reference to a random number generator.
+///
+/// # Returns
+///
+/// * Result<Vec<(Vec2, Vec2)>, Box<dyn std::error::Error>> - The positions and
+///   velocities, or an error if the pattern cannot be used (missing image, no clusters).
+pub fn spawn_states(
+    config: &SpawnConfig,
+    count: usize,
+    world_dims: (f32, f32),
+    rng: &mut impl Rng,
+) -> Result<Vec<(Vec2, Vec2)>, Box<dyn std::error::Error>> {
+    let positions = spawn_positions(&config.pattern, count, world_dims, rng)?;
+    Ok(positions
+        .into_iter()
+        .map(|pos| (pos, initial_velocity(&config.velocity, pos, rng)))
+        .collect())
+}
+
+/// Places the boids following a spawn pattern.
+fn spawn_positions(
+    pattern: &SpawnPatternConfig,
+    count: usize,
+    world_dims: (f32, f32),
+    rng: &mut impl Rng,
+) -> Result<Vec<Vec2>, Box<dyn std::error::Error>> {
+    let positions = match pattern {
+        SpawnPatternConfig::Uniform { inset } => {
+            let inset = inset.unwrap_or(1.0 / 8.0).clamp(0.0, 0.49);
+            let (w, h) = world_dims;
+            (0..count)
+                .map(|_| {
+                    Vec2::new(
+                        rng.random_range(w * inset..=w * (1.0 - inset)),
+                        rng.random_range(h * inset..=h * (1.0 - inset)),
+                    )
+                })
+                .collect()
+        }
+        SpawnPatternConfig::Clusters { clusters } => {
+            let weights: Vec<f32> = clusters
+                .iter()
+                .map(|c| c.weight.unwrap_or(1.0).max(0.0))
+                .collect();
+            let total: f32 = weights.iter().sum();
+            if total <= 0.0 {
+                return Err("spawn clusters need at least one cluster of positive weight".into());
+            }
+            (0..count)
+                .map(|_| {
+                    // Pick a cluster in proportion to its weight
+                    let mut pick = rng.random::<f32>() * total;
+                    let index = weights
+                        .iter()
+                        .position(|&w| {
+                            pick -= w;
+                            pick < 0.0
+                        })
+                        .unwrap_or(clusters.len() - 1);
+                    let cluster = &clusters[index];
+                    let offset = match Normal::new(0.0, cluster.sigma.abs()) {
+                        Ok(normal) => Vec2::new(normal.sample(rng), normal.sample(rng)),
+                        Err(_) => Vec2::ZERO,
+                    };
+                    Vec2::new(cluster.x, cluster.y) + offset
+                })
+                .collect()
+        }
+        SpawnPatternConfig::Ring {
+            x,
+            y,
+            radius,
+            width,
+        } => (0..count)
+            .map(|_| {
+                let angle = rng.random::<f32>() * 2.0 * std::f32::consts::PI;
+                let r = radius + (rng.random::<f32>() - 0.5) * width;
+                Vec2::new(*x, *y) + Vec2::from_angle(angle) * r
+            })
+            .collect(),
+        SpawnPatternConfig::Grid {
+            x,
+            y,
+            spacing,
+            columns,
+        } => {
+            let columns = columns
+                .unwrap_or((count as f32).sqrt().ceil() as usize)
+                .max(1);
+            let rows = count.div_ceil(columns);
+            // Offset of the lattice so that it is centred on (x, y)
+            let half = Vec2::new(columns as f32 - 1.0, rows as f32 - 1.0) * 0.5;
+            (0..count)
+                .map(|k| {
+                    let cell = Vec2::new((k % columns) as f32, (k / columns) as f32);
+                    Vec2::new(*x, *y) + (cell - half) * *spacing
+                })
+                .collect()
+        }
+        SpawnPatternConfig::Image { path, x, y, width } => {
+            let source = MorphSourceConfig::Image { path: path.clone() };
+            let TargetShape::Area {
+                mask,
+                width: image_width,
+                height: image_height,
+            } = TargetShape::load(&source)?
+            else {
+                return Err("spawn image must be an image".into());
+            };
+            let filled: Vec<usize> = (0..mask.len()).filter(|&i| mask[i]).collect();
+            if filled.is_empty() {
+                return Err(format!("spawn image '{}' has no filled pixels", path).into());
+            }
+            // Random points of random filled pixels, the image scaled to width around (x, y)
+            let scale = width / image_width.max(1) as f32;
+            let half = Vec2::new(image_width as f32, image_height as f32) * 0.5;
+            (0..count)
+                .map(|_| {
+                    let i = filled[rng.random_range(0..filled.len())];
+                    let pixel = Vec2::new((i % image_width) as f32, (i / image_width) as f32);
+                    let jitter = Vec2::new(rng.random::<f32>(), rng.random::<f32>());
+                    Vec2::new(*x, *y) + (pixel + jitter - half) * scale
+                })
+                .collect()
+        }
+    };
+    Ok(positions)
+}
+
+/// Returns the initial velocity of a boid.
+///
+/// # Arguments
+///
+/// * 'velocity' - The SpawnVelocityConfig read from boids.yaml.
+/// * 'pos' - The initial position of the boid.
+/// * 'rng' - A mutable reference to a random number generator.
+///
+/// # Returns
+///
+/// * 'Vec2' - The initial velocity.
+pub fn initial_velocity(velocity: &SpawnVelocityConfig, pos: Vec2, rng: &mut impl Rng) -> Vec2 {
+    let random_heading = |rng: &mut dyn rand::RngCore| {
+        Vec2::from_angle(rng.random::<f32>() * 2.0 * std::f32::consts::PI)
+    };
+    match *velocity {
+        SpawnVelocityConfig::Random => random_heading(rng),
+        SpawnVelocityConfig::Aligned { heading, speed } => {
+            Vec2::from_angle(heading.to_radians()) * speed
+        }
+        SpawnVelocityConfig::Vortex {
+            x,
+            y,
+            speed,
+            clockwise,
+        } => {
+            // Tangent to the circle around the center (y points down on screen)
+            let tangent = (pos - Vec2::new(x, y)).perp().try_normalize();
+            let tangent = tangent.unwrap_or_else(|| random_heading(rng));
+            if clockwise {
+                tangent * speed
+            } else {
+                -tangent * speed
+            }
+        }
+        SpawnVelocityConfig::Radial { x, y, speed } => {
+            let outward = (pos - Vec2::new(x, y)).try_normalize();
+            outward.unwrap_or_else(|| random_heading(rng)) * speed
+        }
+    }
+}
@@ -0,0 +1,109 @@
+// src/startle.rs
+// Startle cascades: boids get alarmed (by a predator, an event or at random),
+// make a fast escape turn, and spread the alarm to their neighbors with some
+// probability and delay, so that waves of turning run through the flock.
+
+use ggez::glam::Vec2;
+use rand::Rng;
+
+// --- AlarmState Enum Definition ---
+
+#[derive(Debug, Clone, Copy, Default)]
+pub enum AlarmState {
+    #[default]
+    Calm,
+    // Exposed to an alarmed neighbor, becomes alarmed when the delay runs out
+    Pending {
+        steps_left: u32,
+        cascade: usize,
+        escape: Vec2,
+    },
+    // Escaping along the escape direction
+    Alarmed {
+        steps_left: u32,
+        cascade: usize,
+        escape: Vec2,
+    },
+    // Recovering, cannot be alarmed again until the end of the period
+    Refractory {
+        steps_left: u32,
+    },
+}
+
+// --- AlarmState Implementation ---
+
+impl AlarmState {
+    /// Returns true if the boid can be alarmed.
+    pub fn is_calm(&self) -> bool {
+        matches!(self, AlarmState::Calm)
+    }
+
+    /// Returns the cascade and escape direction of an alarmed boid, None otherwise.
+    pub fn escape(&self) -> Option<(usize, Vec2)> {
+        match *self {
+            AlarmState::Alarmed {
+                cascade, escape, ..
+            } => Some((cascade, escape)),
+            _ => None,
+        }
+    }
+
+    /// Returns the cascade the boid is part of while pending or alarmed.
+    pub fn cascade(&self) -> Option<usize> {
+        match *self {
+            AlarmState::Pending { cascade, .. } | AlarmState::Alarmed { cascade, .. } => {
+                Some(cascade)
+            }
+            _ => None,
+        }
+    }
+}
+
+// --- Exposure Struct Definition ---
+
+// Alarmed neighbors seen by a calm boid during a step
+#[derive(Debug, Clone, Copy)]
+pub struct Exposure {
+    pub alarmed_neighbors: usize,
+    pub cascade: usize, // cascade of one of the alarmed neighbors
+    pub escape: Vec2,   // escape direction of that neighbor
+}
+
+/// Collects the alarmed neighbors of a calm boid.
+///
+/// # Arguments
+///
+/// * 'neighbors' - The alarm states of the boid's neighbors.
+///
+/// # Returns
+///
+/// * 'Option<Exposure>' - The exposure, or None if no neighbor is alarmed.
+pub fn exposure<'a>(neighbors: impl Iterator<Item = &'a AlarmState>) -> Option<Exposure> {
+    let mut exposure: Option<Exposure> = None;
+    for (cascade, escape) in neighbors.filter_map(|state| state.escape()) {
+        match &mut exposure {
+            Some(exposure) => exposure.alarmed_neighbors += 1,
+            None => {
+                exposure = Some(Exposure {
+                    alarmed_neighbors: 1,
+                    cascade,
+                    escape,
+                })
+            }
+        }
+    }
+    exposure
+}
+
+/// Returns the direction of an escape turn: the heading turned by the given
+/// angle to a random side.
+///
+/// # Arguments
+///
+/// * 'vel' - The boid's velocity.
+/// * 'turn_angle' - The turn angle, in degrees.
+/// * 'rng' - A mutable reference to a random number generator.
+pub fn escape_turn(vel: Vec2, turn_angle: f32, rng: &mut impl Rng) -> Vec2 {
+    let side = if rng.random::<bool>() { 1.0 } else { -1.0 };
+    Vec2::from_angle(side * turn_angle.to_radians()).rotate(vel.try_normalize().unwrap_or(Vec2::X))
+}
@@ -0,0 +1,137 @@
+// src/stats.rs
+// Per-step statistics recorded by the simulator (population over time, collisions, order),
+// foraging statistics (food source depletion, per-boid intake) and startle cascades.
+
+use ggez::glam::Vec2;
+use uuid::Uuid;
+
+use crate::config::BehaviorState;
+
+// --- StepStats Struct Definition ---
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct StepStats {
+    pub step: u64,            // simulation step the stats were recorded at
+    pub population: usize,    // number of boids alive at the end of the step
+    pub births: usize,        // offspring created during the step
+    pub deaths: usize,        // boids removed during the step
+    pub overlaps: usize,      // overlapping pairs found before collision resolution
+    pub polarization: f32,    // length of the average heading (1.0 = fully aligned)
+    pub alarmed: usize,       // boids alarmed at the end of the step
+    pub formation_error: f32, // mean distance of the formation members from their slots
+    pub morph_error: f32,     // mean distance of the targeted boids from their morph targets
+    pub roosting: usize,      // boids landed on their roost at the end of the step
+    pub opacity: f32,         // mean fraction of the visual field covered (projection model)
+    pub groups: usize,        // number of family groups
+    pub group_spread: f32,    // mean distance of the group members from their group center
+    pub group_distance: f32,  // mean distance from each group center to the closest other one
+    pub emitted: usize,       // boids spawned by the emitters during the step
+    pub absorbed: usize,      // boids removed by the sinks during the step
+    // boids in each behavioural state, by BehaviorState index
+    pub states: [usize; BehaviorState::COUNT],
+}
+
+// --- FoodPatchStats Struct Definition ---
+
+// Depletion state of one food source
+#[derive(Debug, Clone, Copy)]
+pub struct FoodPatchStats {
+    pub step: u64,        // simulation step the stats were recorded at
+    pub patch: usize,     // index of the food source in the config
+    pub amount: f32,      // food left
+    pub consumed: f32,    // total food eaten since the start
+    pub empty_steps: u64, // number of steps the source spent depleted
+    pub feeders: usize,   // boids inside the source
+}
+
+impl FoodPatchStats {
+    /// Returns the CSV header matching csv_row.
+    pub fn csv_header() -> String {
+        "step,patch,amount,consumed,empty_steps,feeders".to_string()
+    }
+
+    /// Formats the statistics as a CSV row.
+    pub fn csv_row(&self) -> String {
+        format!(
+            "{},{},{},{},{},{}",
+            self.step, self.patch, self.amount, self.consumed, self.empty_steps, self.feeders
+        )
+    }
+}
+
+// --- IntakeStats Struct Definition ---
+
+// Foraging success of one boid, along with how grouped it is
+#[derive(Debug, Clone, Copy)]
+pub struct IntakeStats {
+    pub id: Uuid,             // boid id
+    pub age: u64,             // steps survived
+    pub energy_gathered: f32, // total energy taken from food sources
+    pub neighbors: usize,     // neighbors within the visible range
+    pub flock_size: usize,    // size of the flock the boid belongs to
+}
+
+impl IntakeStats {
+    /// Returns the CSV header matching csv_row.
+    pub fn csv_header() -> String {
+        "id,age,energy_gathered,intake_rate,neighbors,flock_size".to_string()
+    }
+
+    /// Formats the statistics as a CSV row (the intake rate is per step survived).
+    pub fn csv_row(&self) -> String {
+        let intake_rate = self.energy_gathered / self.age.max(1) as f32;
+        format!(
+            "{},{},{},{},{},{}",
+            self.id, self.age, self.energy_gathered, intake_rate, self.neighbors, self.flock_size
+        )
+    }
+}
+
+// --- CascadeStats Struct Definition ---
+
+// What started a startle cascade
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum StartleTrigger {
+    Random,   // spontaneous alarm
+    Event,    // triggered by the user
+    Predator, // a moving obstacle came close
+}
+
+// Propagation of one startle cascade
+#[derive(Debug, Clone, Copy)]
+pub struct CascadeStats {
+    pub id: usize,               // index of the cascade
+    pub trigger: StartleTrigger, // what started the cascade
+    pub start_step: u64,         // step the first boid got alarmed at
+    pub last_step: u64,          // step the last boid got alarmed at
+    pub origin: Vec2,            // position of the first alarmed boid
+    pub reach: usize,            // number of boids alarmed
+    pub max_distance: f32,       // distance from the origin of the furthest alarmed boid
+    pub finished: bool,          // no boid is pending or alarmed anymore
+}
+
+impl CascadeStats {
+    /// Returns the propagation speed: the distance covered by the cascade per step.
+    pub fn propagation_speed(&self) -> f32 {
+        self.max_distance / (self.last_step - self.start_step).max(1) as f32
+    }
+
+    /// Returns the CSV header matching csv_row.
+    pub fn csv_header() -> String {
+        "cascade,trigger,start_step,duration,reach,max_distance,propagation_speed".to_string()
+    }
+
+    /// Formats the statistics as a CSV row.
+    pub fn csv_row(&self) -> String {
+        format!(
+            "{},{:?},{},{},{},{},{}",
+            self.id,
+            self.trigger,
+            self.start_step,
+            self.last_step - self.start_step,
+            self.reach,
+            self.max_distance,
+            self.propagation_speed()
+        )
+    }
+}
@@ -0,0 +1,187 @@
+// src/timeline.rs
+// Scripted event timeline of a scenario: at given steps, spawn boids, introduce a
+// predator, move an attractor, change a parameter, switch a flocking rule on or off,
+// pause or take a snapshot. The simulator applies the events due at every step.
+
+use serde::Deserialize;
+
+use crate::config::{ObstacleConfig, SpawnPatternConfig, SpawnVelocityConfig};
+
+// --- Rule Enum Definition ---
+
+// A flocking rule that can be switched off by the timeline
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Rule {
+    Separation, // avoidfactor
+    Alignment,  // matchingfactor
+    Cohesion,   // centeringfactor
+    Boundary,   // turnfactor
+}
+
+impl Rule {
+    pub const COUNT: usize = 4;
+
+    /// Returns the position of the rule in the simulator's rule switches.
+    pub fn index(self) -> usize {
+        self as usize
+    }
+}
+
+// --- Attractor Enum Definition ---
+
+// The kind of attractor moved by the timeline
+#[derive(Deserialize, Debug, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+pub enum Attractor {
+    Food,  // a food source (foraging)
+    Roost, // a roost (roosting)
+}
+
+impl Attractor {
+    /// Returns the name of the attractor kind, as written in the scenario file.
+    pub fn name(self) -> &'static str {
+        match self {
+            Attractor::Food => "food",
+            Attractor::Roost => "roost",
+        }
+    }
+}
+
+// --- TimelineAction Enum Definition ---
+
+// What happens at an event, selected with the `event` key
+#[derive(Deserialize, Debug, Clone)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum TimelineAction {
+    // Spawn `count` boids following a spawn pattern and velocity mode
+    Spawn {
+        count: usize,
+        #[serde(default)]
+        pattern: SpawnPatternConfig,
+        #[serde(default)]
+        velocity: SpawnVelocityConfig,
+    },
+    // Add an obstacle (a moving one acts as a predator)
+    Predator {
+        obstacle: ObstacleConfig,
+    },
+    // Move the attractor of the given kind and index (in config order) to (x, y)
+    MoveAttractor {
+        attractor: Attractor,
+        index: usize,
+        x: f32,
+        y: f32,
+    },
+    // Set a numeric BoidsConfig parameter
+    SetParam {
+        name: String,
+        value: f32,
+    },
+    // Switch a flocking rule on or off (flip it if `enabled` is unset)
+    ToggleRule {
+        rule: Rule,
+        enabled: Option<bool>,
+    },
+    // Pause the windowed simulation (ignored by headless runs)
+    Pause,
+    // Write the state of every boid to a CSV scenario file (snapshot_<step>.csv if unset)
+    Snapshot {
+        path: Option<String>,
+    },
+}
+
+// --- TimelineEvent Struct Definition ---
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct TimelineEvent {
+    pub step: u64, // step the event happens at, before the boids move
+    #[serde(flatten)]
+    pub action: TimelineAction,
+}
+
+// --- Timeline Struct Definition ---
+
+// Events not applied yet, in step order
+#[derive(Debug, Clone, Default)]
+pub struct Timeline {
+    events: Vec<TimelineEvent>, // sorted by step, events of a step in file order
+    next: usize,                // index of the first event not applied yet
+}
+
+// --- Timeline Implementation ---
+
+impl Timeline {
+    /// Creates a new Timeline from the events of a scenario.
+    ///
+    /// # Arguments
+    ///
+    /// * 'events' - The events, in any order.
+    ///
+    /// # Returns
+    ///
+    /// * 'Self' - A new Timeline instance.
+    pub fn new(events: &[TimelineEvent]) -> Self {
+        let mut events = events.to_vec();
+        events.sort_by_key(|event| event.step); // Stable: same-step events keep their order
+        Timeline { events, next: 0 }
+    }
+
+    /// Returns the events due at a step (and any earlier ones not applied yet), and
+    /// marks them as applied.
+    pub fn due(&mut self, step: u64) -> Vec<TimelineAction> {
+        let start = self.next;
+        while self.next < self.events.len() && self.events[self.next].step <= step {
+            self.next += 1;
+        }
+        self.events[start..self.next]
+            .iter()
+            .map(|event| event.action.clone())
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event(step: u64, name: &str) -> TimelineEvent {
+        TimelineEvent {
+            step,
+            action: TimelineAction::SetParam {
+                name: name.to_string(),
+                value: 0.0,
+            },
+        }
+    }
+
+    fn names(actions: Vec<TimelineAction>) -> Vec<String> {
+        actions
+            .into_iter()
+            .map(|action| match action {
+                TimelineAction::SetParam { name, .. } => name,
+                other => panic!("unexpected action {:?}", other),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn events_are_sorted_stably_by_step() {
+        let mut timeline =
+            Timeline::new(&[event(5, "c"), event(2, "a"), event(5, "d"), event(2, "b")]);
+        assert!(timeline.due(1).is_empty());
+        assert_eq!(names(timeline.due(2)), ["a", "b"]);
+        assert_eq!(names(timeline.due(5)), ["c", "d"]);
+    }
+
+    #[test]
+    fn skipped_steps_are_caught_up_once() {
+        let mut timeline = Timeline::new(&[event(3, "a"), event(7, "b"), event(20, "c")]);
+        // Jumping past steps 3 and 7 still delivers both, in order
+        assert_eq!(names(timeline.due(10)), ["a", "b"]);
+        assert!(timeline.due(10).is_empty());
+        assert!(timeline.due(19).is_empty());
+        assert_eq!(names(timeline.due(100)), ["c"]);
+        assert!(timeline.due(200).is_empty());
+    }
+}
@@ -0,0 +1,112 @@
+// src/ttc.rs
+// Anticipatory avoidance based on the time to collision (TTC) with each neighbor.
+// Instead of reacting to the current distance, each boid extrapolates the relative
+// motion of its neighbors and steers away from the collisions expected within a horizon.
+
+use ggez::glam::Vec2;
+
+use crate::boids::Boid;
+
+/// Returns the time (in steps) before two bodies collide, if they ever do.
+///
+/// # Arguments
+///
+/// * 'rel_pos' - Position of the first body relative to the second.
+/// * 'rel_vel' - Displacement per step of the first body relative to the second.
+/// * 'combined_radius' - Sum of the two body radii.
+///
+/// # Returns
+///
+/// * 'Option<f32>' - 0.0 if already colliding, None if the bodies never collide.
+pub fn time_to_collision(rel_pos: Vec2, rel_vel: Vec2, combined_radius: f32) -> Option<f32> {
+    let c = rel_pos.length_squared() - combined_radius * combined_radius;
+    if c < 0.0 {
+        return Some(0.0); // Already colliding
+    }
+    let a = rel_vel.length_squared();
+    let b = rel_pos.dot(rel_vel);
+    if b >= 0.0 || a < 1e-9 {
+        return None; // Moving apart or not moving relative to each other
+    }
+    let discriminant = b * b - a * c;
+    if discriminant <= 0.0 {
+        return None; // The paths never get close enough
+    }
+    Some((-b - discriminant.sqrt()) / a)
+}
+
+/// Calculates the velocity change steering the boid away from its imminent collisions.
+/// Each neighbor colliding within the horizon pushes the boid along the direction
+/// separating the two bodies at the predicted collision, with a strength decreasing
+/// linearly with the time to collision.
+///
+/// # Arguments
+///
+/// * 'boid' - The boid avoiding its neighbors.
+/// * 'neighbors' - A slice of references to the neighbors to avoid.
+/// * 'radius' - Body radius of every boid.
+/// * 'horizon' - Collisions further than this many steps are ignored.
+/// * 'strength' - Velocity change for a collision happening now.
+/// * 'dt' - Duration of one step (the config dt).
+///
+/// # Returns
+///
+/// * 'Vec2' - The avoidance velocity change.
+pub fn avoidance_velocity_change(
+    boid: &Boid,
+    neighbors: &[&Boid],
+    radius: f32,
+    horizon: f32,
+    strength: f32,
+    dt: f32,
+) -> Vec2 {
+    let mut delta_v = Vec2::ZERO;
+    for other in neighbors {
+        let rel_pos = boid.pos - other.pos;
+        let rel_vel = (boid.vel - other.vel) * dt;
+        let Some(ttc) = time_to_collision(rel_pos, rel_vel, 2.0 * radius) else {
+            continue;
+        };
+        if ttc >= horizon {
+            continue;
+        }
+        // Direction between the two bodies at the predicted collision
+        let dir = (rel_pos + rel_vel * ttc).normalize_or_zero();
+        delta_v += dir * strength * (1.0 - ttc / horizon);
+    }
+    delta_v
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn overlapping_bodies_collide_now() {
+        let ttc = time_to_collision(Vec2::new(1.0, 1.0), Vec2::new(3.0, 0.0), 2.0);
+        assert_eq!(ttc, Some(0.0));
+    }
+
+    #[test]
+    fn bodies_moving_apart_never_collide() {
+        let ttc = time_to_collision(Vec2::new(10.0, 0.0), Vec2::new(1.0, 0.5), 2.0);
+        assert_eq!(ttc, None);
+    }
+
+    #[test]
+    fn head_on_bodies_collide_when_they_touch() {
+        // 10 px apart closing at 2 px per step, touching once 2 px apart
+        let ttc = time_to_collision(Vec2::new(10.0, 0.0), Vec2::new(-2.0, 0.0), 2.0).unwrap();
+        assert!((ttc - 4.0).abs() < 1e-5, "{}", ttc);
+
+        // Offset paths touch earlier than the closest approach: 3-4-5 at contact
+        let ttc = time_to_collision(Vec2::new(10.0, 3.0), Vec2::new(-1.0, 0.0), 5.0).unwrap();
+        assert!((ttc - 6.0).abs() < 1e-5, "{}", ttc);
+    }
+
+    #[test]
+    fn parallel_paths_too_far_apart_never_collide() {
+        let ttc = time_to_collision(Vec2::new(10.0, 5.0), Vec2::new(-1.0, 0.0), 2.0);
+        assert_eq!(ttc, None);
+    }
+}
@@ -0,0 +1,529 @@
+// src/world_map.rs
+// Bitmap world maps: a PNG whose colours mark walls, spawn zones, goals and
+// parameter regions. Walls are turned into a signed distance field for avoidance
+// and goals into a distance field boids follow around the walls.
+
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+
+use ggez::glam::Vec2;
+use rand::Rng;
+
+use crate::boids::Boid;
+use crate::config::{MapConfig, ParamOverrides};
+
+// --- Legend ---
+const WALL_COLOR: [u8; 3] = [0, 0, 0]; // black
+const SPAWN_COLOR: [u8; 3] = [0, 255, 0]; // green
+const GOAL_COLOR: [u8; 3] = [255, 0, 0]; // red
+const FAR: f64 = 1e20; // "infinite" squared distance for the distance transform
+
+// --- Cell Enum Definition ---
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Cell {
+    Free,
+    Wall,
+    Spawn,
+    Goal,
+    Region(usize), // index in the configured regions
+}
+
+// --- WorldMap Struct Definition ---
+
+pub struct WorldMap {
+    cols: usize,
+    rows: usize,
+    cell_size: f32,
+    cells: Vec<Cell>,        // row-major
+    sdf: Vec<f32>,           // signed distance to the walls (negative inside a wall)
+    goal_distance: Vec<f32>, // path length to the closest goal (INFINITY if unreachable)
+    spawn_points: Vec<Vec2>, // centers of the spawn cells (or of all free cells)
+    regions: Vec<([u8; 3], ParamOverrides)>,
+    wall_distance: f32,
+    wall_factor: f32,
+    goal_factor: f32,
+}
+
+// --- WorldMap Implementation ---
+
+impl WorldMap {
+    /// Loads a world map from the PNG file given in the map config.
+    ///
+    /// # Arguments
+    ///
+    /// * 'config' - The MapConfig read from boids.yaml.
+    ///
+    /// # Returns
+    ///
+    /// * Result<WorldMap, Box<dyn std::error::Error>> - The loaded map, or an error if
+    ///   the image cannot be read.
+    pub fn load(config: &MapConfig) -> Result<WorldMap, Box<dyn std::error::Error>> {
+        let image = image::open(&config.path)?.to_rgba8();
+        let (cols, rows) = (image.width() as usize, image.height() as usize);
+        let regions: Vec<([u8; 3], ParamOverrides)> = config
+            .regions
+            .iter()
+            .map(|region| (region.color, region.overrides))
+            .collect();
+
+        // --- Classify every pixel with the legend ---
+        let cells: Vec<Cell> = image
+            .pixels()
+            .map(|pixel| {
+                let [r, g, b, a] = pixel.0;
+                let rgb = [r, g, b];
+                if a < 128 {
+                    Cell::Free // Transparent pixels are free space
+                } else if rgb == WALL_COLOR {
+                    Cell::Wall
+                } else if rgb == SPAWN_COLOR {
+                    Cell::Spawn
+                } else if rgb == GOAL_COLOR {
+                    Cell::Goal
+                } else if let Some(i) = regions.iter().position(|(color, _)| *color == rgb) {
+                    Cell::Region(i)
+                } else {
+                    Cell::Free
+                }
+            })
+            .collect();
+
+        let mut map = WorldMap {
+            cols,
+            rows,
+            cell_size: config.cell_size,
+            cells,
+            sdf: Vec::new(),
+            goal_distance: Vec::new(),
+            spawn_points: Vec::new(),
+            regions,
+            wall_distance: config.wall_distance,
+            wall_factor: config.wall_factor,
+            goal_factor: config.goal_factor,
+        };
+        map.sdf = map.compute_sdf();
+        map.goal_distance = map.compute_goal_distance();
+
+        // Spawn in the spawn cells, or anywhere free if the map has none
+        map.spawn_points = map.cell_centers(|cell| cell == Cell::Spawn);
+        if map.spawn_points.is_empty() {
+            map.spawn_points = map.cell_centers(|cell| cell != Cell::Wall);
+        }
+        Ok(map)
+    }
+
+    /// Returns the centers of the cells matching a predicate.
+    fn cell_centers(&self, predicate: impl Fn(Cell) -> bool) -> Vec<Vec2> {
+        (0..self.cells.len())
+            .filter(|&i| predicate(self.cells[i]))
+            .map(|i| self.cell_center(i % self.cols, i / self.cols))
+            .collect()
+    }
+
+    /// Returns the size (width, height) of the world covered by the map.
+    pub fn world_dims(&self) -> (f32, f32) {
+        (
+            self.cols as f32 * self.cell_size,
+            self.rows as f32 * self.cell_size,
+        )
+    }
+
+    /// Returns the world position of the center of a cell.
+    fn cell_center(&self, col: usize, row: usize) -> Vec2 {
+        Vec2::new(col as f32 + 0.5, row as f32 + 0.5) * self.cell_size
+    }
+
+    /// Returns the (column, row) of the cell containing a point, clamped to the map.
+    fn cell_of(&self, p: Vec2) -> (usize, usize) {
+        let c = (p.x / self.cell_size)
+            .floor()
+            .clamp(0.0, (self.cols - 1) as f32);
+        let r = (p.y / self.cell_size)
+            .floor()
+            .clamp(0.0, (self.rows - 1) as f32);
+        (c as usize, r as usize)
+    }
+
+    /// Computes the signed distance field of the walls with an exact Euclidean
+    /// distance transform, from both the wall cells and the free cells.
+    fn compute_sdf(&self) -> Vec<f32> {
+        let is_wall: Vec<bool> = self.cells.iter().map(|c| *c == Cell::Wall).collect();
+        let to_wall = self.distance_transform(&is_wall);
+        let is_free: Vec<bool> = is_wall.iter().map(|w| !w).collect();
+        let to_free = self.distance_transform(&is_free);
+
+        // Distances between cell centers, minus half a cell to approximate the boundary
+        (0..self.cells.len())
+            .map(|i| {
+                let d = if is_wall[i] {
+                    -(to_free[i].sqrt() - 0.5)
+                } else {
+                    to_wall[i].sqrt() - 0.5
+                };
+                d as f32 * self.cell_size
+            })
+            .collect()
+    }
+
+    /// Squared Euclidean distance (in cells) from every cell to the closest feature cell,
+    /// computed with the separable algorithm of Felzenszwalb and Huttenlocher.
+    fn distance_transform(&self, feature: &[bool]) -> Vec<f64> {
+        let (cols, rows) = (self.cols, self.rows);
+        let mut grid: Vec<f64> = feature.iter().map(|&f| if f { 0.0 } else { FAR }).collect();
+
+        // Columns, then rows
+        let mut column = vec![0.0; rows];
+        for c in 0..cols {
+            for r in 0..rows {
+                column[r] = grid[r * cols + c];
+            }
+            let transformed = distance_transform_1d(&column);
+            for r in 0..rows {
+                grid[r * cols + c] = transformed[r];
+            }
+        }
+        for r in 0..rows {
+            let transformed = distance_transform_1d(&grid[r * cols..(r + 1) * cols]);
+            grid[r * cols..(r + 1) * cols].copy_from_slice(&transformed);
+        }
+        grid
+    }
+
+    /// Computes the path length from every free cell to the closest goal cell,
+    /// moving between the 8 neighboring cells around the walls (Dijkstra).
+    fn compute_goal_distance(&self) -> Vec<f32> {
+        let mut distance = vec![f32::INFINITY; self.cells.len()];
+        // Costs are positive floats, whose bit patterns sort like the values
+        let mut heap = BinaryHeap::new();
+        for (i, cell) in self.cells.iter().enumerate() {
+            if *cell == Cell::Goal {
+                distance[i] = 0.0;
+                heap.push(Reverse((0.0f32.to_bits(), i)));
+            }
+        }
+
+        while let Some(Reverse((cost_bits, i))) = heap.pop() {
+            let cost = f32::from_bits(cost_bits);
+            if cost > distance[i] {
+                continue; // Stale entry
+            }
+            let (c, r) = ((i % self.cols) as i64, (i / self.cols) as i64);
+            for (dc, dr) in NEIGHBORS_8 {
+                let (nc, nr) = (c + dc, r + dr);
+                if nc < 0 || nr < 0 || nc >= self.cols as i64 || nr >= self.rows as i64 {
+                    continue;
+                }
+                let j = nr as usize * self.cols + nc as usize;
+                if self.cells[j] == Cell::Wall {
+                    continue;
+                }
+                let step = if dc != 0 && dr != 0 {
+                    std::f32::consts::SQRT_2
+                } else {
+                    1.0
+                };
+                let new_cost = cost + step;
+                if new_cost < distance[j] {
+                    distance[j] = new_cost;
+                    heap.push(Reverse((new_cost.to_bits(), j)));
+                }
+            }
+        }
+        distance
+    }
+
+    /// Returns the signed distance to the walls at a point, interpolated between cell centers.
+    pub fn signed_distance(&self, p: Vec2) -> f32 {
+        // Continuous cell coordinates relative to the cell centers
+        let x = (p.x / self.cell_size - 0.5).clamp(0.0, (self.cols - 1) as f32);
+        let y = (p.y / self.cell_size - 0.5).clamp(0.0, (self.rows - 1) as f32);
+        let (c0, r0) = (x.floor() as usize, y.floor() as usize);
+        let (c1, r1) = ((c0 + 1).min(self.cols - 1), (r0 + 1).min(self.rows - 1));
+        let (fx, fy) = (x - c0 as f32, y - r0 as f32);
+        let at = |c: usize, r: usize| self.sdf[r * self.cols + c];
+        let top = at(c0, r0) * (1.0 - fx) + at(c1, r0) * fx;
+        let bottom = at(c0, r1) * (1.0 - fx) + at(c1, r1) * fx;
+        top * (1.0 - fy) + bottom * fy
+    }
+
+    /// Returns the distance along a ray to the first wall, or None if the ray is clear.
+    /// Marches along the ray by the distance to the closest wall (sphere tracing).
+    ///
+    /// # Arguments
+    ///
+    /// * 'origin' - The start of the ray.
+    /// * 'dir' - The unit direction of the ray.
+    /// * 'max_dist' - The length of the ray.
+    pub fn ray_distance(&self, origin: Vec2, dir: Vec2, max_dist: f32) -> Option<f32> {
+        let min_step = self.cell_size * 0.5; // The field is only known per cell
+        let mut t = 0.0;
+        while t <= max_dist {
+            let dist = self.signed_distance(origin + dir * t);
+            if dist <= 0.0 {
+                return Some(t);
+            }
+            t += dist.max(min_step);
+        }
+        None
+    }
+
+    /// Returns the direction away from the closest wall (gradient of the distance field).
+    fn wall_normal(&self, p: Vec2) -> Vec2 {
+        let h = self.cell_size;
+        let gradient = Vec2::new(
+            self.signed_distance(p + Vec2::new(h, 0.0))
+                - self.signed_distance(p - Vec2::new(h, 0.0)),
+            self.signed_distance(p + Vec2::new(0.0, h))
+                - self.signed_distance(p - Vec2::new(0.0, h)),
+        );
+        gradient.normalize_or_zero()
+    }
+
+    /// Calculates the velocity change steering a boid away from the walls and
+    /// along the shortest path towards the closest goal.
+    ///
+    /// # Arguments
+    ///
+    /// * 'boid' - The boid to steer.
+    ///
+    /// # Returns
+    ///
+    /// * 'Vec2' - The steering velocity change.
+    pub fn steering_velocity_change(&self, boid: &Boid) -> Vec2 {
+        let mut delta_v = Vec2::ZERO;
+
+        // --- Walls ---
+        let dist = self.signed_distance(boid.pos);
+        if dist < self.wall_distance {
+            let strength = self.wall_factor * (1.0 - dist / self.wall_distance);
+            delta_v += self.wall_normal(boid.pos) * strength;
+        }
+
+        // --- Goals ---
+        // Head to the neighboring cell closest to a goal
+        if self.goal_factor != 0.0 {
+            let (c, r) = self.cell_of(boid.pos);
+            let mut best = (self.goal_distance[r * self.cols + c], None);
+            for (dc, dr) in NEIGHBORS_8 {
+                let (nc, nr) = (c as i64 + dc, r as i64 + dr);
+                if nc < 0 || nr < 0 || nc >= self.cols as i64 || nr >= self.rows as i64 {
+                    continue;
+                }
+                let d = self.goal_distance[nr as usize * self.cols + nc as usize];
+                if d < best.0 {
+                    best = (d, Some((nc as usize, nr as usize)));
+                }
+            }
+            if let Some((nc, nr)) = best.1 {
+                let dir = (self.cell_center(nc, nr) - boid.pos).normalize_or_zero();
+                delta_v += dir * self.goal_factor;
+            }
+        }
+        delta_v
+    }
+
+    /// Pushes a boid that ended inside a wall back out along the distance field.
+    pub fn push_out(&self, boid: &mut Boid) {
+        let dist = self.signed_distance(boid.pos);
+        if dist >= 0.0 {
+            return;
+        }
+        let normal = self.wall_normal(boid.pos);
+        boid.pos += normal * -dist;
+        let inward = boid.vel.dot(normal);
+        if inward < 0.0 {
+            boid.vel -= normal * inward;
+        }
+    }
+
+    /// Returns the parameter overrides of the region containing a point, if any.
+    pub fn region_at(&self, p: Vec2) -> Option<&ParamOverrides> {
+        let (c, r) = self.cell_of(p);
+        match self.cells[r * self.cols + c] {
+            Cell::Region(i) => Some(&self.regions[i].1),
+            _ => None,
+        }
+    }
+
+    /// Returns a random point of a random spawn cell (of a free cell if the map has no spawn zone).
+    pub fn random_spawn_point(&self, rng: &mut impl Rng) -> Option<Vec2> {
+        if self.spawn_points.is_empty() {
+            return None;
+        }
+        let center = self.spawn_points[rng.random_range(0..self.spawn_points.len())];
+        let jitter = Vec2::new(rng.random::<f32>() - 0.5, rng.random::<f32>() - 0.5);
+        Some(center + jitter * self.cell_size)
+    }
+
+    /// Returns the map as an RGBA image (one pixel per cell) for drawing:
+    /// walls opaque, spawn zones, goals and regions faint, free space transparent.
+    ///
+    /// # Returns
+    ///
+    /// * '(Vec<u8>, u32, u32)' - The pixels, width and height.
+    pub fn overlay_rgba(&self) -> (Vec<u8>, u32, u32) {
+        let pixels = self
+            .cells
+            .iter()
+            .flat_map(|cell| match *cell {
+                Cell::Free => [0, 0, 0, 0],
+                Cell::Wall => [90, 90, 100, 255],
+                Cell::Spawn => [0, 255, 0, 40],
+                Cell::Goal => [255, 0, 0, 60],
+                Cell::Region(i) => {
+                    let [r, g, b] = self.regions[i].0;
+                    [r, g, b, 40]
+                }
+            })
+            .collect();
+        (pixels, self.cols as u32, self.rows as u32)
+    }
+
+    /// Returns the size of a map cell in world units.
+    pub fn cell_size(&self) -> f32 {
+        self.cell_size
+    }
+}
+
+// The 8 neighboring cells offsets (column, row)
+const NEIGHBORS_8: [(i64, i64); 8] = [
+    (-1, -1),
+    (0, -1),
+    (1, -1),
+    (-1, 0),
+    (1, 0),
+    (-1, 1),
+    (0, 1),
+    (1, 1),
+];
+
+/// One-dimensional squared distance transform of a sampled function
+/// (lower envelope of parabolas rooted at every sample).
+fn distance_transform_1d(f: &[f64]) -> Vec<f64> {
+    let n = f.len();
+    let mut d = vec![0.0; n];
+    let mut v = vec![0usize; n]; // Locations of the parabolas of the lower envelope
+    let mut z = vec![0.0f64; n + 1]; // Boundaries between the parabolas
+    let mut k = 0;
+    z[0] = f64::NEG_INFINITY;
+    z[1] = f64::INFINITY;
+
+    let intersection = |q: usize, p: usize| -> f64 {
+        let (qf, pf) = (q as f64, p as f64);
+        ((f[q] + qf * qf) - (f[p] + pf * pf)) / (2.0 * qf - 2.0 * pf)
+    };
+
+    for q in 1..n {
+        let mut s = intersection(q, v[k]);
+        while s <= z[k] {
+            k -= 1;
+            s = intersection(q, v[k]);
+        }
+        k += 1;
+        v[k] = q;
+        z[k] = s;
+        z[k + 1] = f64::INFINITY;
+    }
+
+    k = 0;
+    for (q, out) in d.iter_mut().enumerate() {
+        while z[k + 1] < q as f64 {
+            k += 1;
+        }
+        let dq = q as f64 - v[k] as f64;
+        *out = dq * dq + f[v[k]];
+    }
+    d
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+
+    /// Builds a map from rows of '#' (wall), 'G' (goal) and '.' (free) cells.
+    fn map_from(rows: &[&str]) -> WorldMap {
+        let cells = rows
+            .iter()
+            .flat_map(|row| row.chars())
+            .map(|c| match c {
+                '#' => Cell::Wall,
+                'G' => Cell::Goal,
+                _ => Cell::Free,
+            })
+            .collect();
+        WorldMap {
+            cols: rows[0].len(),
+            rows: rows.len(),
+            cell_size: 1.0,
+            cells,
+            sdf: Vec::new(),
+            goal_distance: Vec::new(),
+            spawn_points: Vec::new(),
+            regions: Vec::new(),
+            wall_distance: 0.0,
+            wall_factor: 0.0,
+            goal_factor: 0.0,
+        }
+    }
+
+    #[test]
+    fn distance_transform_matches_brute_force() {
+        let (cols, rows) = (13, 9);
+        let map = map_from(&vec![".".repeat(cols).as_str(); rows]);
+        let mut rng = StdRng::seed_from_u64(7);
+        let feature: Vec<bool> = (0..cols * rows)
+            .map(|_| rng.random::<f32>() < 0.15)
+            .collect();
+
+        let transformed = map.distance_transform(&feature);
+        for (i, &squared) in transformed.iter().enumerate() {
+            let (c, r) = ((i % cols) as f64, (i / cols) as f64);
+            let brute = (0..cols * rows)
+                .filter(|&j| feature[j])
+                .map(|j| {
+                    let (fc, fr) = ((j % cols) as f64, (j / cols) as f64);
+                    (c - fc).powi(2) + (r - fr).powi(2)
+                })
+                .fold(f64::INFINITY, f64::min);
+            assert_eq!(squared, brute, "cell ({}, {})", c, r);
+        }
+    }
+
+    #[test]
+    fn rays_stop_at_the_walls() {
+        let mut map = map_from(&["..........", "..........", "........##", ".........."]);
+        map.sdf = map.compute_sdf();
+        let origin = Vec2::new(0.5, 2.5);
+        let hit = map.ray_distance(origin, Vec2::X, 20.0).unwrap();
+        assert!((7.0..=8.0).contains(&hit), "hit at {}", hit); // Wall face at x = 8
+        assert_eq!(map.ray_distance(origin, Vec2::X, 5.0), None);
+        assert_eq!(map.ray_distance(Vec2::new(0.5, 0.5), Vec2::X, 20.0), None);
+    }
+
+    #[test]
+    fn goal_distance_goes_around_walls() {
+        let map = map_from(&[
+            "G....", "####.", ".....", "###.#", "###.#", "##..#", "#####",
+        ]);
+        let distance = map.compute_goal_distance();
+        let at = |c: usize, r: usize| distance[r * map.cols + c];
+        let sqrt2 = std::f32::consts::SQRT_2;
+
+        assert_eq!(at(0, 0), 0.0);
+        assert!((at(4, 1) - (3.0 + sqrt2)).abs() < 1e-5);
+        // Through the gap at the end of the wall, then back along the row below
+        assert!((at(0, 2) - (6.0 + 2.0 * sqrt2)).abs() < 1e-5);
+        assert!(at(0, 1).is_infinite(), "wall cells are never reached");
+    }
+
+    #[test]
+    fn enclosed_cells_are_unreachable() {
+        let map = map_from(&["G.#...", "..#.#.", "..#...", "..####"]);
+        let distance = map.compute_goal_distance();
+        assert!(distance[map.cols + 5].is_infinite());
+        assert!(distance[map.cols + 1].is_finite());
+    }
+}
@@ -0,0 +1,104 @@
+// src/zones.rs
+// Zones of the world (circles or rectangles) where some boid parameters are
+// overridden, e.g. a slow zone or a fog zone with a short visible range.
+
+use ggez::glam::Vec2;
+
+use crate::config::{ParamOverrides, ZoneConfig};
+use crate::shapes::Shape;
+
+// --- Zone Struct Definition ---
+
+#[derive(Debug, Clone, Copy)]
+pub struct Zone {
+    pub shape: Shape,
+    blend: f32, // width of the transition band outside the shape
+    overrides: ParamOverrides,
+}
+
+// --- Zone Implementation ---
+
+impl Zone {
+    /// Creates a new Zone from its configuration.
+    ///
+    /// # Arguments
+    ///
+    /// * 'config' - The ZoneConfig read from boids.yaml.
+    ///
+    /// # Returns
+    ///
+    /// * 'Self' - A new Zone instance.
+    pub fn new(config: &ZoneConfig) -> Self {
+        Zone {
+            shape: Shape::new(&config.shape),
+            blend: config.blend,
+            overrides: config.overrides,
+        }
+    }
+
+    /// Returns how much the zone applies at a point: 1.0 inside the shape, easing
+    /// down to 0.0 at the outer edge of the transition band.
+    ///
+    /// # Arguments
+    ///
+    /// * 'p' - The point to test.
+    pub fn weight_at(&self, p: Vec2) -> f32 {
+        let dist = self.shape.signed_distance(p);
+        if dist <= 0.0 {
+            return 1.0;
+        }
+        if dist >= self.blend {
+            return 0.0;
+        }
+        // Smoothstep from the boundary to the edge of the band
+        let t = 1.0 - dist / self.blend;
+        t * t * (3.0 - 2.0 * t)
+    }
+
+    /// Returns the parameter overrides of the zone.
+    pub fn overrides(&self) -> &ParamOverrides {
+        &self.overrides
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::ShapeConfig;
+
+    fn zone(blend: f32) -> Zone {
+        Zone::new(&ZoneConfig {
+            shape: ShapeConfig::Circle {
+                x: 0.0,
+                y: 0.0,
+                radius: 10.0,
+            },
+            blend,
+            overrides: ParamOverrides::default(),
+        })
+    }
+
+    #[test]
+    fn weight_eases_smoothly_across_the_band() {
+        let zone = zone(4.0);
+        let weight = |x: f32| zone.weight_at(Vec2::new(x, 0.0));
+        assert_eq!(weight(5.0), 1.0);
+        assert_eq!(weight(10.0), 1.0);
+        assert_eq!(weight(14.0), 0.0);
+        assert_eq!(weight(20.0), 0.0);
+        assert!((weight(12.0) - 0.5).abs() < 1e-6);
+
+        // Flat at both ends of the band (zero slope of the smoothstep)
+        let h = 0.01;
+        assert!(1.0 - weight(10.0 + h) < 1e-3);
+        assert!(weight(14.0 - h) < 1e-3);
+        assert!(weight(11.0) > weight(13.0));
+    }
+
+    #[test]
+    fn zero_blend_gives_a_hard_edge() {
+        let zone = zone(0.0);
+        assert_eq!(zone.weight_at(Vec2::new(9.9, 0.0)), 1.0);
+        assert_eq!(zone.weight_at(Vec2::new(10.1, 0.0)), 0.0);
+    }
+}